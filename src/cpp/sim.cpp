@@ -408,7 +408,7 @@ private:
 		{
 			auto goal = world.goals.at(i);
 
-			if(distance_squared(racer_pos.x, racer_pos.y, goal.x, goal.y) <= square(world.racer.r + goal.r))
+			if(distance_squared(racer_pos.x, racer_pos.y, goal.x, goal.y) < square(world.racer.r + goal.r))
 			{
 				if (!goal_states.at(i))
 				{
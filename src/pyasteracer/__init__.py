@@ -240,7 +240,7 @@ class Simulation:
         new_goal_reached = False
 
         for i, goal in enumerate(self.goals):
-            if euclidean_distance(self.racer.x, self.racer.y, goal.x, goal.y) <= (self.racer.radius + goal.radius):
+            if euclidean_distance(self.racer.x, self.racer.y, goal.x, goal.y) < (self.racer.radius + goal.radius):
                 if not self.reached_goals[i]:
                     new_goal_reached = True
 
@@ -0,0 +1,316 @@
+//! Map generator, a port of `pyasteracer/generator.py`.
+//!
+//! The maps are not the same as the Python ones (the random number generators and the noise
+//! differ), but they are generated the same way and are reproducible for a given seed.
+use crate::simulation::*;
+use rand::prelude::*;
+use rand::rngs::StdRng;
+use std::io::stdout;
+
+pub const ASTEROID_RADIUS: SizeType = 30_000;
+pub const RACER_RADIUS: SizeType = 1000;
+
+/// How many candidate positions to try for each object, picking the one furthest from the
+/// other asteroids (so they are spread out more evenly).
+const CANDIDATES: usize = 10;
+
+/// Scale of the noise; the larger, the larger the asteroid clusters.
+const NOISE_FREQUENCY: f64 = (1 << 17) as f64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MapType {
+    Sprint,
+    Marathon,
+}
+
+impl MapType {
+    /// The name of the example map of this type (`maps/<name>.txt`).
+    pub fn name(self) -> &'static str {
+        match self {
+            MapType::Sprint => "sprint",
+            MapType::Marathon => "marathon",
+        }
+    }
+}
+
+/// The map types of the tests, test `n` is `TESTS[n - 1]`.
+pub const TESTS: [MapType; 2] = [MapType::Sprint, MapType::Marathon];
+
+/// Seeded 2D Perlin noise.
+struct Noise {
+    permutation: [u8; 512],
+}
+
+impl Noise {
+    fn new(rng: &mut impl Rng) -> Self {
+        let mut values: Vec<u8> = (0..=255).collect();
+        values.shuffle(rng);
+
+        let mut permutation = [0; 512];
+        for (i, value) in permutation.iter_mut().enumerate() {
+            *value = values[i % 256];
+        }
+
+        Self { permutation }
+    }
+
+    fn gradient(&self, x: i64, y: i64, dx: f64, dy: f64) -> f64 {
+        let hash = self.permutation
+            [self.permutation[x.rem_euclid(256) as usize] as usize + y.rem_euclid(256) as usize];
+
+        match hash % 8 {
+            0 => dx + dy,
+            1 => dx - dy,
+            2 => -dx + dy,
+            3 => -dx - dy,
+            4 => dx,
+            5 => -dx,
+            6 => dy,
+            _ => -dy,
+        }
+    }
+
+    /// Noise value at the given coordinates, roughly in `[-1, 1]`.
+    fn get(&self, x: f64, y: f64) -> f64 {
+        fn fade(t: f64) -> f64 {
+            t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+        }
+
+        fn lerp(a: f64, b: f64, t: f64) -> f64 {
+            a + (b - a) * t
+        }
+
+        let (x0, y0) = (x.floor(), y.floor());
+        let (dx, dy) = (x - x0, y - y0);
+        let (x0, y0) = (x0 as i64, y0 as i64);
+
+        let (u, v) = (fade(dx), fade(dy));
+
+        let bottom = lerp(
+            self.gradient(x0, y0, dx, dy),
+            self.gradient(x0 + 1, y0, dx - 1.0, dy),
+            u,
+        );
+        let top = lerp(
+            self.gradient(x0, y0 + 1, dx, dy - 1.0),
+            self.gradient(x0 + 1, y0 + 1, dx - 1.0, dy - 1.0),
+            u,
+        );
+
+        lerp(bottom, top, v).clamp(-1.0, 1.0)
+    }
+
+    /// The probability that an asteroid is at those coordinates.
+    fn asteroid_probability(&self, x: PosType, y: PosType) -> f64 {
+        (self.get(x as f64 / NOISE_FREQUENCY, y as f64 / NOISE_FREQUENCY) + 1.0) / 2.0
+    }
+}
+
+/// Return true if an object collides with any of the other objects.
+fn collides(object: &Asteroid, others: &[Asteroid]) -> bool {
+    others.iter().any(|other| {
+        euclidean_distance(object.x, object.y, other.x, other.y) <= object.radius + other.radius
+    })
+}
+
+/// Generate a map of the given type. The same seed always generates the same map.
+pub fn generate_map(map_type: MapType, seed: u64) -> Map {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let noise = Noise::new(&mut rng);
+
+    let mut safe_zone = RACER_RADIUS * 100;
+
+    let (asteroid_count, goal_count, max_x, center) = match map_type {
+        MapType::Sprint => {
+            let max_x = 500_000;
+            (
+                600,
+                1,
+                max_x,
+                (-max_x + safe_zone / 2, -max_x + safe_zone / 2),
+            )
+        }
+        MapType::Marathon => {
+            safe_zone *= 2;
+            (1500, 70, 1_200_000, (0, 0))
+        }
+    };
+
+    let max_y = max_x;
+
+    let in_zone = |x: PosType, y: PosType, (center_x, center_y): (PosType, PosType)| {
+        (center_x - safe_zone / 2..=center_x + safe_zone / 2).contains(&x)
+            && (center_y - safe_zone / 2..=center_y + safe_zone / 2).contains(&y)
+    };
+
+    // the racer's starting position, so nothing is generated on top of it
+    let start = [Asteroid {
+        x: center.0,
+        y: center.1,
+        radius: RACER_RADIUS,
+    }];
+
+    let mut asteroids: Vec<Asteroid> = vec![];
+    let mut goals: Vec<Goal> = vec![];
+
+    let mut get_asteroid_or_goal = |asteroids: &[Asteroid]| {
+        let mut max_distance = -1;
+        let mut max_position = (0, 0);
+
+        for _ in 0..CANDIDATES {
+            // no spawn camping
+            let (x, y) = loop {
+                let x = rng.random_range(-max_x..=max_x);
+                let y = rng.random_range(-max_y..=max_y);
+
+                if noise.asteroid_probability(x, y) < 0.25 {
+                    continue;
+                }
+
+                // for sprint, also make the other side clear (goal is there)
+                if map_type == MapType::Sprint && in_zone(x, y, (-center.0, -center.1)) {
+                    continue;
+                }
+
+                if in_zone(x, y, center) {
+                    continue;
+                }
+
+                break (x, y);
+            };
+
+            let distance = asteroids
+                .iter()
+                .map(|asteroid| euclidean_distance(asteroid.x, asteroid.y, x, y))
+                .min()
+                .unwrap_or(PosType::MAX);
+
+            if distance > max_distance {
+                max_distance = distance;
+                max_position = (x, y);
+            }
+        }
+
+        let (x, y) = max_position;
+
+        Asteroid {
+            x,
+            y,
+            radius: (ASTEROID_RADIUS as f64 * noise.asteroid_probability(x, y)) as SizeType,
+        }
+    };
+
+    while asteroids.len() < asteroid_count {
+        let asteroid = get_asteroid_or_goal(&asteroids);
+
+        // the racer must not start inside an asteroid
+        if collides(&asteroid, &start) {
+            continue;
+        }
+
+        asteroids.push(asteroid);
+    }
+
+    if map_type == MapType::Sprint {
+        goals.push(Goal {
+            x: -center.0,
+            y: -center.1,
+            radius: ASTEROID_RADIUS / 2,
+        });
+    } else {
+        while goals.len() < goal_count {
+            let goal = get_asteroid_or_goal(&asteroids);
+
+            if collides(&goal, &start) || collides(&goal, &asteroids) || collides(&goal, &goals) {
+                continue;
+            }
+
+            goals.push(goal);
+        }
+    }
+
+    Map::new(
+        Racer {
+            x: start[0].x,
+            y: start[0].y,
+            vx: 0,
+            vy: 0,
+            radius: start[0].radius,
+        },
+        asteroids,
+        goals,
+        BoundingBox {
+            min_x: -max_x,
+            min_y: -max_y,
+            max_x,
+            max_y,
+        },
+    )
+}
+
+/// Generator for [`crate::opendata::OpenData`], see [`TESTS`].
+///
+/// The test number must be one of the tests, which `OpenData` checks when it's given
+/// [`TESTS`]`.len()` as its test count.
+pub fn generate(test: usize, seed: u64) {
+    let map_type = TESTS[test - 1];
+
+    generate_map(map_type, seed)
+        .to_writer(stdout().lock())
+        .expect("Failed writing the map!");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generation_reproducible() {
+        for map_type in TESTS {
+            let map = generate_map(map_type, 42).description();
+
+            assert_eq!(map, generate_map(map_type, 42).description());
+            assert_ne!(map, generate_map(map_type, 43).description());
+        }
+    }
+
+    #[test]
+    fn test_generated_maps_valid() {
+        for (map_type, asteroid_count, goal_count) in
+            [(MapType::Sprint, 600, 1), (MapType::Marathon, 1500, 70)]
+        {
+            let map = generate_map(map_type, 0);
+
+            assert_eq!(map.asteroids.len(), asteroid_count);
+            assert_eq!(map.goals.len(), goal_count);
+
+            let racer = Asteroid {
+                x: map.racer.x,
+                y: map.racer.y,
+                radius: map.racer.radius,
+            };
+
+            // the racer doesn't start in an asteroid or a goal
+            assert!(!collides(&racer, &map.asteroids));
+            assert!(!collides(&racer, &map.goals));
+
+            for (i, goal) in map.goals.iter().enumerate() {
+                assert!(!collides(goal, &map.asteroids));
+                assert!(!collides(goal, &map.goals[i + 1..]));
+            }
+
+            // everything is within the bounding box
+            for object in map.asteroids.iter().chain(&map.goals) {
+                assert!(map.bbox.min_x <= object.x && object.x <= map.bbox.max_x);
+                assert!(map.bbox.min_y <= object.y && object.y <= map.bbox.max_y);
+            }
+
+            // and the map can be loaded again
+            let mut buffer = vec![];
+            map.to_writer(&mut buffer).unwrap();
+            let loaded = Map::from_reader(buffer.as_slice()).unwrap();
+
+            assert_eq!(loaded.description(), map.description());
+        }
+    }
+}
@@ -0,0 +1,159 @@
+use crate::simulation::{Asteroid, PosType, SizeType};
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+/// A uniform grid over circular objects (asteroids or goals), used to only check the objects
+/// that are close to the racer.
+///
+/// Objects are bucketed by their own bounding box, so the grid doesn't depend on the racer's
+/// radius and can be queried for circles of any size.
+#[derive(Debug, Clone)]
+pub struct Grid {
+    cells: HashMap<(PosType, PosType), Vec<usize>>,
+    cell_size: PosType,
+}
+
+impl Grid {
+    pub fn new(objects: &[Asteroid], cell_size: PosType) -> Self {
+        let mut grid = Self {
+            cells: HashMap::new(),
+            cell_size,
+        };
+
+        for (i, object) in objects.iter().enumerate() {
+            let (min_x, min_y) =
+                grid.coordinate_to_grid(object.x - object.radius, object.y - object.radius);
+            let (max_x, max_y) =
+                grid.coordinate_to_grid(object.x + object.radius, object.y + object.radius);
+
+            for grid_x in min_x..=max_x {
+                for grid_y in min_y..=max_y {
+                    // objects are added in order, so each cell stays sorted
+                    grid.cells.entry((grid_x, grid_y)).or_default().push(i);
+                }
+            }
+        }
+
+        grid
+    }
+
+    /// Floor division, so that every cell is the same size; truncating would make the cells
+    /// around the axes twice as wide.
+    fn coordinate_to_grid(&self, x: PosType, y: PosType) -> (PosType, PosType) {
+        (x.div_euclid(self.cell_size), y.div_euclid(self.cell_size))
+    }
+
+    /// Indexes of the objects that could intersect a circle, in increasing order.
+    ///
+    /// This includes every object whose bounding box overlaps the circle's bounding box (and
+    /// possibly a few more), so an object not returned is never within `radius` of it.
+    pub fn query(&self, x: PosType, y: PosType, radius: SizeType) -> Cow<'_, [usize]> {
+        let (min_x, min_y) = self.coordinate_to_grid(x - radius, y - radius);
+        let (max_x, max_y) = self.coordinate_to_grid(x + radius, y + radius);
+
+        // the usual case for small racers, no need to merge anything
+        if min_x == max_x && min_y == max_y {
+            return match self.cells.get(&(min_x, min_y)) {
+                Some(indexes) => Cow::Borrowed(indexes),
+                None => Cow::Borrowed(&[]),
+            };
+        }
+
+        let mut indexes = vec![];
+
+        for grid_x in min_x..=max_x {
+            for grid_y in min_y..=max_y {
+                if let Some(cell) = self.cells.get(&(grid_x, grid_y)) {
+                    indexes.extend_from_slice(cell);
+                }
+            }
+        }
+
+        indexes.sort_unstable();
+        indexes.dedup();
+
+        Cow::Owned(indexes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::euclidean_distance;
+    use rand::prelude::*;
+    use rand::rngs::StdRng;
+
+    #[test]
+    fn test_cells_around_axes() {
+        let grid = Grid::new(&[], 10);
+
+        assert_eq!(grid.coordinate_to_grid(0, 9), (0, 0));
+        assert_eq!(grid.coordinate_to_grid(-1, 10), (-1, 1));
+        assert_eq!(grid.coordinate_to_grid(-10, -11), (-1, -2));
+        assert_eq!(grid.coordinate_to_grid(-5, 5), (-1, 0));
+
+        // all cells are the same size, including those around the origin
+        for cell in -3..3 {
+            let size = (-40..40)
+                .filter(|&x| grid.coordinate_to_grid(x, x) == (cell, cell))
+                .count();
+
+            assert_eq!(size, 10);
+        }
+    }
+
+    #[test]
+    fn test_query_straddling_axes() {
+        // small asteroids right next to the axes, in all four quadrants and on the axes
+        let objects = [(-6, -6), (6, -6), (-6, 6), (6, 6), (0, -6), (-6, 0), (0, 0)]
+            .map(|(x, y)| Asteroid { x, y, radius: 2 });
+
+        let grid = Grid::new(&objects, 10);
+
+        for x in -20..=20 {
+            for y in -20..=20 {
+                let candidates = grid.query(x, y, 1);
+
+                for (i, object) in objects.iter().enumerate() {
+                    if euclidean_distance(x, y, object.x, object.y) <= 1 + object.radius {
+                        assert!(candidates.contains(&i), "{:?} not found", object);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_query_finds_all_intersecting() {
+        let mut rng = StdRng::seed_from_u64(0);
+
+        let objects = (0..500)
+            .map(|_| Asteroid {
+                x: rng.random_range(-100_000..100_000),
+                y: rng.random_range(-100_000..100_000),
+                radius: rng.random_range(1..20_000),
+            })
+            .collect::<Vec<_>>();
+
+        let grid = Grid::new(&objects, 10_000);
+
+        for _ in 0..10_000 {
+            let (x, y) = (
+                rng.random_range(-150_000..150_000),
+                rng.random_range(-150_000..150_000),
+            );
+            let radius = rng.random_range(0..30_000);
+
+            let candidates = grid.query(x, y, radius);
+
+            assert!(candidates.windows(2).all(|pair| pair[0] < pair[1]));
+
+            for (i, object) in objects.iter().enumerate() {
+                // the same check as the collisions use, so it includes isqrt rounding
+                if euclidean_distance(x, y, object.x, object.y) <= radius + object.radius {
+                    assert!(candidates.contains(&i), "{:?} not found", object);
+                }
+            }
+        }
+    }
+}
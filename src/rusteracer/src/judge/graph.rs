@@ -0,0 +1,273 @@
+//! Druhý způsob odevzdání: místo instrukcí cesta po grafu asteroidů (viz `graphs/` a
+//! [`super::graph_path`]).
+//!
+//! Výstup má na prvním řádku počet vrcholů a pak na každém řádku číslo jednoho vrcholu.
+//! Cesta začíná ve vrcholu závodníka (0), ten se nepíše. Body se počítají z délky cesty.
+use super::{JudgeOptions, Length, Message, OutputReadError, Scoring, SubmissionReader};
+use crate::opendata::judge::Verdict;
+use crate::simulation::*;
+use crate::solve::AsteroidGraph;
+use std::collections::HashSet;
+use std::io::BufRead;
+
+#[derive(Debug)]
+pub enum ValidationError {
+    VertexOutOfBounds(usize),    // vrchol s tímhle číslem v grafu není
+    VertexBlocked(usize, usize), // vrchol leží v asteroidu (vrchol, asteroid)
+    EdgeNotFound(usize, usize),  // mezi vrcholy není hrana
+}
+
+#[derive(Debug)]
+pub(super) enum PathError {
+    Read(OutputReadError),
+    Validation(ValidationError),
+}
+
+impl From<OutputReadError> for PathError {
+    fn from(e: OutputReadError) -> Self {
+        PathError::Read(e)
+    }
+}
+
+impl From<ValidationError> for PathError {
+    fn from(e: ValidationError) -> Self {
+        PathError::Validation(e)
+    }
+}
+
+/// Průběh cesty po grafu.
+///
+/// Délky hran se sčítají přesně a zaokrouhlují se až celkové délky, jinak by se u dlouhých
+/// cest sčítala chyba zaokrouhlení každé hrany.
+#[derive(Debug, Default)]
+struct Walk {
+    length: usize,                  // délka celé cesty
+    finished_length: Option<usize>, // délka cesty do dosažení posledního cíle
+    reached_goals: Vec<bool>,
+}
+
+fn walk<TReader: BufRead>(
+    map: &Map,
+    graph: &AsteroidGraph,
+    reader: TReader,
+    max_length: usize,
+) -> Result<Walk, PathError> {
+    let (vertices, edges, vertex_objects) = graph;
+
+    let edges = (edges.iter())
+        .map(|&(u, v)| (u.min(v), u.max(v)))
+        .collect::<HashSet<_>>();
+
+    let mut walk = Walk {
+        reached_goals: vec![false; map.goals.len()],
+        ..Walk::default()
+    };
+
+    if map.goals.is_empty() {
+        walk.finished_length = Some(0);
+    }
+
+    let mut submission = SubmissionReader::new(reader, max_length)?;
+
+    // cesta začíná v závodníkovi
+    let mut current = 0;
+    let mut length = 0.0;
+
+    while let Some((_, [vertex])) = submission.next_numbers::<usize, 1>()? {
+        let &(x, y) = vertices
+            .get(vertex)
+            .ok_or(ValidationError::VertexOutOfBounds(vertex))?;
+
+        // stejně jako při generování grafu, vrchol nesmí být uvnitř asteroidu
+        if let Some(asteroid) = map.asteroid_at(x, y) {
+            return Err(ValidationError::VertexBlocked(vertex, asteroid).into());
+        }
+
+        // zůstat na místě jde vždycky
+        if vertex != current && !edges.contains(&(current.min(vertex), current.max(vertex))) {
+            return Err(ValidationError::EdgeNotFound(current, vertex).into());
+        }
+
+        let (current_x, current_y) = vertices[current];
+        length += ((x - current_x) as f64).hypot((y - current_y) as f64);
+        walk.length = length.round() as usize;
+        current = vertex;
+
+        if let ('G', goal) = vertex_objects[vertex] {
+            walk.reached_goals[goal] = true;
+        }
+
+        if walk.finished_length.is_none() && walk.reached_goals.iter().all(|&b| b) {
+            walk.finished_length = Some(walk.length);
+        }
+    }
+
+    Ok(walk)
+}
+
+/// Ohodnotí cestu po grafu přečtenou z `reader`.
+pub(super) fn judge_path<TReader: BufRead>(
+    map: &Map,
+    graph: &AsteroidGraph,
+    scoring: &Scoring,
+    reader: TReader,
+    options: JudgeOptions,
+) -> Verdict {
+    let language = options.language;
+
+    let walk = match walk(map, graph, reader, options.max_instructions) {
+        Ok(walk) => walk,
+        Err(PathError::Read(ref e @ OutputReadError::IoError(ref error))) => {
+            // podrobnosti jsou jen v JSON verdiktu, soutěžící dostane obecnou zprávu
+            return Verdict::internal_error()
+                .message(&Message::from(e).text(language))
+                .detail("read_error", error.to_string());
+        }
+        Err(e) => return Verdict::wrong().message(&Message::from(&e).text(language)),
+    };
+
+    let reached = walk.reached_goals.iter().filter(|&&b| b).count();
+
+    let verdict = if let Some(finished_length) = walk.finished_length {
+        let points = scoring.points(match scoring.length {
+            Length::Finished => finished_length,
+            Length::Submitted => walk.length,
+        });
+
+        Verdict::correct()
+            .override_points(points)
+            .message(&Message::Success(points).text(language))
+    } else {
+        let unreached = (walk.reached_goals.iter().enumerate())
+            .filter_map(|(i, &b)| if !b { Some(i + 1) } else { None })
+            .collect();
+
+        let message = Message::UnreachedGoals(unreached).text(language);
+
+        // i částečné body jde dát jen správnému řešení
+        match scoring.partial_points(reached, walk.reached_goals.len()) {
+            Some(points) if points > 0.0 => {
+                Verdict::correct().override_points(points).message(&message)
+            }
+            _ => Verdict::wrong().message(&message),
+        }
+    };
+
+    verdict
+        .detail("mode", "graph")
+        .detail("length", walk.length)
+        .detail("finished_length", walk.finished_length)
+        .detail("goals", walk.reached_goals.len())
+        .detail("goals_reached", reached)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::opendata::judge::VerdictType;
+    use crate::solve;
+    use std::path::PathBuf;
+
+    fn load(name: &str) -> (Map, AsteroidGraph) {
+        let map = Map::load(&PathBuf::from(format!("../../maps/{}.txt", name))).unwrap();
+        let graph =
+            solve::load_asteroid_graph(&PathBuf::from(format!("../../graphs/{}.txt", name)))
+                .unwrap();
+
+        (map, graph)
+    }
+
+    fn submission(path: &[usize]) -> String {
+        let lines = path.iter().map(|v| v.to_string()).collect::<Vec<_>>();
+
+        format!("{}\n{}\n", path.len(), lines.join("\n"))
+    }
+
+    #[test]
+    fn test_shortest_path_is_correct() {
+        let (map, graph) = load("sprint");
+        let (vertices, edges, vertex_objects) = &graph;
+
+        // sprint only has one goal, so the shortest path to it is a solution
+        let (length, path) = solve::shortest_path(vertices, edges, vertex_objects).unwrap();
+
+        let walk = walk(&map, &graph, submission(&path[1..]).as_bytes(), usize::MAX).unwrap();
+
+        assert!(walk.reached_goals.iter().all(|&b| b));
+        assert_eq!(walk.finished_length, Some(walk.length));
+        assert_eq!(walk.length, length.round() as usize);
+
+        let scoring = "mode=graph\nbaseline=1000000".parse().unwrap();
+        let verdict = judge_path(
+            &map,
+            &graph,
+            &scoring,
+            submission(&path[1..]).as_bytes(),
+            JudgeOptions::default(),
+        );
+
+        assert_eq!(verdict.verdict_type(), VerdictType::Correct);
+    }
+
+    #[test]
+    fn test_invalid_paths() {
+        let (map, graph) = load("test");
+        let (vertices, edges, _) = &graph;
+
+        let error = |path: &[usize]| walk(&map, &graph, submission(path).as_bytes(), usize::MAX);
+
+        assert!(matches!(
+            error(&[vertices.len()]),
+            Err(PathError::Validation(ValidationError::VertexOutOfBounds(v))) if v == vertices.len()
+        ));
+
+        // a vertex that isn't connected to the start
+        let unconnected = (1..vertices.len())
+            .find(|&v| !edges.contains(&(0, v)) && !edges.contains(&(v, 0)))
+            .unwrap();
+
+        assert!(matches!(
+            error(&[unconnected]),
+            Err(PathError::Validation(ValidationError::EdgeNotFound(0, v))) if v == unconnected
+        ));
+
+        // staying in place is fine, but doesn't reach anything
+        let walk = error(&[0, 0]).unwrap();
+        assert_eq!(walk.length, 0);
+        assert_eq!(walk.finished_length, None);
+
+        assert!(matches!(error(&[]).map(|walk| walk.length), Ok(0)));
+        assert!(matches!(
+            walk_from_str(&map, &graph, "2\n0\n"),
+            Err(PathError::Read(OutputReadError::MissingInstructionsError(
+                2, 1
+            )))
+        ));
+    }
+
+    #[test]
+    fn test_blocked_vertex() {
+        let (map, mut graph) = load("test");
+
+        // move a vertex into the first asteroid and connect it to the start
+        let asteroid = map.asteroids[0];
+        graph.0[1] = (asteroid.x, asteroid.y);
+        graph.1.push((0, 1));
+
+        assert!(matches!(
+            walk_from_str(&map, &graph, "1\n1\n"),
+            Err(PathError::Validation(ValidationError::VertexBlocked(1, 0)))
+        ));
+
+        // the asteroid's edge still blocks, just outside of it doesn't
+        graph.0[1] = (asteroid.x + asteroid.radius, asteroid.y);
+        assert!(matches!(
+            walk_from_str(&map, &graph, "1\n1\n"),
+            Err(PathError::Validation(ValidationError::VertexBlocked(1, 0)))
+        ));
+    }
+
+    fn walk_from_str(map: &Map, graph: &AsteroidGraph, input: &str) -> Result<Walk, PathError> {
+        walk(map, graph, input.as_bytes(), usize::MAX)
+    }
+}
@@ -0,0 +1,554 @@
+//! Katalog zpráv judge, aby šel použít i na anglických soutěžích.
+use super::graph::{PathError, ValidationError};
+use super::report::Report;
+use super::OutputReadError;
+use std::env;
+
+/// Proměnná prostředí s jazykem zpráv (`cs` nebo `en`).
+pub const LANGUAGE_VAR: &str = "JUDGE_LANGUAGE";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Language {
+    #[default]
+    Czech,
+    English,
+}
+
+impl Language {
+    #[allow(dead_code)] // zatím jen v testech
+    pub const ALL: [Language; 2] = [Language::Czech, Language::English];
+
+    pub fn from_code(code: &str) -> Option<Self> {
+        match code.trim().to_lowercase().as_str() {
+            "cs" | "cz" | "czech" => Some(Language::Czech),
+            "en" | "english" => Some(Language::English),
+            _ => None,
+        }
+    }
+
+    /// Jazyk z [`LANGUAGE_VAR`]; když není nastavený (nebo mu nerozumíme), tak čeština.
+    pub fn from_env() -> Self {
+        env::var(LANGUAGE_VAR)
+            .ok()
+            .and_then(|code| Self::from_code(&code))
+            .unwrap_or_default()
+    }
+}
+
+/// Všechny zprávy, které judge může vrátit.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Message {
+    BadTestName(String),  // jméno testu, ze kterého nejde udělat cesta k mapě
+    MissingMap,           // není vstup ani adresář s datasety
+    MapError(String),     // chyba při načítání mapy
+    ScoringError(String), // chyba při načítání bodování
+    MissingGraph,         // graf k mapě neexistuje
+    GraphError(String),   // chyba při načítání grafu
+    SettingError(String), // chybné nastavení judge v proměnné prostředí
+    ReadError,            // nepodařilo se přečíst odevzdaný výstup
+    FirstLineError,
+    NumberTypeError(usize),
+    NumberCountError(usize),
+    LengthError(usize),
+    MissingInstructionsError(usize, usize), // očekávaný a skutečný počet
+    ExtraLineError(usize),
+    TooManyInstructionsError(usize), // maximální počet instrukcí
+    LineLengthError(usize),
+    EncodingError(usize),
+    PathFirstLineError,
+    VertexTypeError(usize),
+    VertexCountError(usize),
+    MissingVerticesError(usize, usize), // očekávaný a skutečný počet
+    PathExtraLineError(usize),
+    TooManyVerticesError(usize), // maximální počet vrcholů
+    VertexOutOfBounds(usize),
+    VertexBlocked(usize, usize),
+    EdgeNotFound(usize, usize),
+    UnreachedGoals(Vec<usize>), // čísla nedosažených cílů (od jedničky)
+    Success(f64),               // body
+    Report(Report),             // podrobný průběh letu
+}
+
+impl From<&OutputReadError> for Message {
+    fn from(error: &OutputReadError) -> Self {
+        match *error {
+            OutputReadError::IoError(_) => Message::ReadError,
+            OutputReadError::FirstLineError => Message::FirstLineError,
+            OutputReadError::NumberTypeError(line) => Message::NumberTypeError(line),
+            OutputReadError::NumberCountError(line) => Message::NumberCountError(line),
+            OutputReadError::LengthError(line) => Message::LengthError(line),
+            OutputReadError::MissingInstructionsError(expected, actual) => {
+                Message::MissingInstructionsError(expected, actual)
+            }
+            OutputReadError::ExtraLineError(line) => Message::ExtraLineError(line),
+            OutputReadError::TooManyInstructionsError(max) => {
+                Message::TooManyInstructionsError(max)
+            }
+            OutputReadError::LineLengthError(line) => Message::LineLengthError(line),
+            OutputReadError::EncodingError(line) => Message::EncodingError(line),
+        }
+    }
+}
+
+impl From<&ValidationError> for Message {
+    fn from(error: &ValidationError) -> Self {
+        match *error {
+            ValidationError::VertexOutOfBounds(vertex) => Message::VertexOutOfBounds(vertex),
+            ValidationError::VertexBlocked(vertex, asteroid) => {
+                Message::VertexBlocked(vertex, asteroid)
+            }
+            ValidationError::EdgeNotFound(u, v) => Message::EdgeNotFound(u, v),
+        }
+    }
+}
+
+/// Cesta se čte stejně jako instrukce, jen je na každém řádku vrchol, tak o něm mluví i zprávy.
+impl From<&PathError> for Message {
+    fn from(error: &PathError) -> Self {
+        match error {
+            PathError::Read(error) => match *error {
+                OutputReadError::FirstLineError => Message::PathFirstLineError,
+                OutputReadError::NumberTypeError(line) => Message::VertexTypeError(line),
+                OutputReadError::NumberCountError(line) => Message::VertexCountError(line),
+                OutputReadError::MissingInstructionsError(expected, actual) => {
+                    Message::MissingVerticesError(expected, actual)
+                }
+                OutputReadError::ExtraLineError(line) => Message::PathExtraLineError(line),
+                OutputReadError::TooManyInstructionsError(max) => {
+                    Message::TooManyVerticesError(max)
+                }
+                // chyby čtení souboru a řádků jsou stejné jako u instrukcí
+                _ => Message::from(error),
+            },
+            PathError::Validation(error) => Message::from(error),
+        }
+    }
+}
+
+impl Message {
+    pub fn text(&self, language: Language) -> String {
+        match language {
+            Language::Czech => self.czech(),
+            Language::English => self.english(),
+        }
+    }
+
+    fn czech(&self) -> String {
+        match self {
+            Message::BadTestName(name) => format!("Špatné jméno úlohy '{}'", name),
+            Message::MissingMap => {
+                "Chybí vstup i adresář s datasety, není odkud načíst mapu.".to_string()
+            }
+            Message::MapError(e) => format!("Chyba při načítání mapy: {}", e),
+            Message::ScoringError(e) => format!("Chyba při načítání bodování: {}", e),
+            Message::MissingGraph => "Chybí graf asteroidů k mapě (graphs/<mapa>.txt).".to_string(),
+            Message::GraphError(e) => format!("Chyba při načítání grafu: {}", e),
+            Message::SettingError(e) => format!("Chybné nastavení judge: {}", e),
+            Message::ReadError => "Chyba při čtení souboru.".to_string(),
+            Message::FirstLineError => "První řádek neobsahuje počet instrukcí!".to_string(),
+            Message::NumberTypeError(line) => {
+                format!("Instrukce na řádku {} nemá správný typ!", line)
+            }
+            Message::NumberCountError(line) => {
+                format!("Instrukce na řádku {} nemá správný počet čísel!", line)
+            }
+            Message::LengthError(line) => format!(
+                "Euklidovská vzdálenost instrukce na řádku {} je větší než povolená!",
+                line
+            ),
+            Message::MissingInstructionsError(expected, actual) => format!(
+                "Nesedí počet instrukcí, má jich být {}, ale je jich jen {}!",
+                expected, actual
+            ),
+            Message::ExtraLineError(line) => {
+                format!("Na řádku {} je něco navíc za poslední instrukcí!", line)
+            }
+            Message::TooManyInstructionsError(max) => {
+                format!("Instrukcí je víc než povolených {}!", max)
+            }
+            Message::LineLengthError(line) => format!("Řádek {} je příliš dlouhý!", line),
+            Message::EncodingError(line) => format!("Řádek {} není v UTF-8!", line),
+            Message::PathFirstLineError => "První řádek neobsahuje počet vrcholů!".to_string(),
+            Message::VertexTypeError(line) => {
+                format!("Na řádku {} není číslo vrcholu!", line)
+            }
+            Message::VertexCountError(line) => {
+                format!("Na řádku {} má být právě jeden vrchol!", line)
+            }
+            Message::MissingVerticesError(expected, actual) => format!(
+                "Nesedí počet vrcholů cesty, má jich být {}, ale je jich jen {}!",
+                expected, actual
+            ),
+            Message::PathExtraLineError(line) => {
+                format!("Na řádku {} je něco navíc za posledním vrcholem!", line)
+            }
+            Message::TooManyVerticesError(max) => {
+                format!("Cesta má víc vrcholů než povolených {}!", max)
+            }
+            Message::VertexOutOfBounds(vertex) => format!("Vrchol {} v grafu není!", vertex),
+            Message::VertexBlocked(vertex, asteroid) => {
+                format!("Vrchol {} je uvnitř asteroidu {}!", vertex, asteroid + 1)
+            }
+            Message::EdgeNotFound(u, v) => format!("Mezi vrcholy {} a {} není hrana!", u, v),
+            Message::UnreachedGoals(goals) => format!(
+                "Po provedení instrukcí nebyly dosaženy cíle {}!",
+                join(goals, Language::Czech)
+            ),
+            Message::Success(points) => format!("Úspěšný let! Body: {}", points),
+            Message::Report(report) => format!(
+                "Dosažené cíle: {}/{}{}; kolize: {}; závodník: {}",
+                report.reached_goals(),
+                report.goal_ticks.len(),
+                unreached(report, Language::Czech),
+                report.collisions,
+                racer(report),
+            ),
+        }
+    }
+
+    fn english(&self) -> String {
+        match self {
+            Message::BadTestName(name) => format!("Invalid test name '{}'", name),
+            Message::MissingMap => {
+                "Neither the input nor the dataset directory is available to load the map from."
+                    .to_string()
+            }
+            Message::MapError(e) => format!("Error while loading the map: {}", e),
+            Message::ScoringError(e) => format!("Error while loading the scoring: {}", e),
+            Message::MissingGraph => {
+                "The asteroid graph of the map (graphs/<map>.txt) is missing.".to_string()
+            }
+            Message::GraphError(e) => format!("Error while loading the graph: {}", e),
+            Message::SettingError(e) => format!("Invalid judge setting: {}", e),
+            Message::ReadError => "Error while reading the file.".to_string(),
+            Message::FirstLineError => {
+                "The first line doesn't contain the number of instructions!".to_string()
+            }
+            Message::NumberTypeError(line) => {
+                format!("The instruction on line {} has an invalid type!", line)
+            }
+            Message::NumberCountError(line) => format!(
+                "The instruction on line {} doesn't have the right amount of numbers!",
+                line
+            ),
+            Message::LengthError(line) => format!(
+                "The Euclidean length of the instruction on line {} is larger than allowed!",
+                line
+            ),
+            Message::MissingInstructionsError(expected, actual) => format!(
+                "The number of instructions doesn't match, expected {} but there are only {}!",
+                expected, actual
+            ),
+            Message::ExtraLineError(line) => {
+                format!("Line {} is extra, after the last instruction!", line)
+            }
+            Message::TooManyInstructionsError(max) => {
+                format!("There are more instructions than the allowed {}!", max)
+            }
+            Message::LineLengthError(line) => format!("Line {} is too long!", line),
+            Message::EncodingError(line) => format!("Line {} is not valid UTF-8!", line),
+            Message::PathFirstLineError => {
+                "The first line doesn't contain the number of vertices!".to_string()
+            }
+            Message::VertexTypeError(line) => {
+                format!("Line {} doesn't contain a vertex number!", line)
+            }
+            Message::VertexCountError(line) => {
+                format!("Line {} should contain exactly one vertex!", line)
+            }
+            Message::MissingVerticesError(expected, actual) => format!(
+                "The number of path vertices doesn't match, expected {} but there are only {}!",
+                expected, actual
+            ),
+            Message::PathExtraLineError(line) => {
+                format!("Line {} is extra, after the last vertex!", line)
+            }
+            Message::TooManyVerticesError(max) => {
+                format!("The path has more vertices than the allowed {}!", max)
+            }
+            Message::VertexOutOfBounds(vertex) => {
+                format!("Vertex {} is not in the graph!", vertex)
+            }
+            Message::VertexBlocked(vertex, asteroid) => {
+                format!("Vertex {} is inside asteroid {}!", vertex, asteroid + 1)
+            }
+            Message::EdgeNotFound(u, v) => {
+                format!("There is no edge between vertices {} and {}!", u, v)
+            }
+            Message::UnreachedGoals(goals) => format!(
+                "Goals {} were not reached after executing the instructions!",
+                join(goals, Language::English)
+            ),
+            Message::Success(points) => format!("Successful flight! Points: {}", points),
+            Message::Report(report) => format!(
+                "Reached goals: {}/{}{}; collisions: {}; racer: {}",
+                report.reached_goals(),
+                report.goal_ticks.len(),
+                unreached(report, Language::English),
+                report.collisions,
+                racer(report),
+            ),
+        }
+    }
+}
+
+fn racer(report: &Report) -> String {
+    let racer = report.racer;
+
+    format!(
+        "x={} y={} vx={} vy={}",
+        racer.x, racer.y, racer.vx, racer.vy
+    )
+}
+
+/// Kolik položek nejvýš vypisujeme; zpráva má mít nejvýš 255 bajtů a maraton má 70 cílů.
+const LISTED_GOALS: usize = 3;
+
+/// Prvních [`LISTED_GOALS`] položek oddělených čárkou, za nimi `more`, pokud jich je víc.
+fn join_listed(items: &[String], more: &str) -> String {
+    let mut joined = items[..items.len().min(LISTED_GOALS)].join(", ");
+
+    if items.len() > LISTED_GOALS {
+        joined += more;
+    }
+
+    joined
+}
+
+fn join(goals: &[usize], language: Language) -> String {
+    let more = match language {
+        Language::Czech => format!(" a další (celkem {})", goals.len()),
+        Language::English => format!(" and more ({} in total)", goals.len()),
+    };
+    let goals = goals
+        .iter()
+        .map(|goal| goal.to_string())
+        .collect::<Vec<_>>();
+
+    join_listed(&goals, &more)
+}
+
+/// Nedosažené cíle do průběhu letu, s nejmenší vzdáleností, pokud ji průběh sledoval.
+fn unreached(report: &Report, language: Language) -> String {
+    let goals = (report.unreached_goals().into_iter())
+        .map(|goal| match &report.closest_approaches {
+            Some(closest) => format!("{}: {}", goal, closest[goal - 1]),
+            None => goal.to_string(),
+        })
+        .collect::<Vec<_>>();
+
+    if goals.is_empty() {
+        return String::new();
+    }
+
+    let heading = match (language, report.closest_approaches.is_some()) {
+        (Language::Czech, true) => "nedosažené (nejblíž)",
+        (Language::Czech, false) => "nedosažené",
+        (Language::English, true) => "unreached (closest)",
+        (Language::English, false) => "unreached",
+    };
+
+    // kolik jich je, je vidět z počtu dosažených
+    format!(", {} {}", heading, join_listed(&goals, ", …"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::opendata::judge::MESSAGE_LIMIT;
+    use crate::simulation::Racer;
+
+    /// Jedna zpráva od každé varianty.
+    fn all_messages() -> Vec<Message> {
+        let messages = vec![
+            Message::BadTestName("../sprint".to_string()),
+            Message::MissingMap,
+            Message::MapError("line 3".to_string()),
+            Message::ScoringError("curve".to_string()),
+            Message::MissingGraph,
+            Message::GraphError("not found".to_string()),
+            Message::SettingError("JUDGE_MAX_INSTRUCTIONS=lots".to_string()),
+            Message::ReadError,
+            Message::FirstLineError,
+            Message::NumberTypeError(2),
+            Message::NumberCountError(3),
+            Message::LengthError(4),
+            Message::MissingInstructionsError(10, 8),
+            Message::ExtraLineError(12),
+            Message::TooManyInstructionsError(1000),
+            Message::LineLengthError(5),
+            Message::EncodingError(6),
+            Message::PathFirstLineError,
+            Message::VertexTypeError(2),
+            Message::VertexCountError(3),
+            Message::MissingVerticesError(10, 8),
+            Message::PathExtraLineError(12),
+            Message::TooManyVerticesError(1000),
+            Message::VertexOutOfBounds(1000),
+            Message::VertexBlocked(10, 2),
+            Message::EdgeNotFound(0, 5),
+            Message::UnreachedGoals(vec![1, 3]),
+            Message::Success(12.0),
+            Message::Report(Report {
+                ticks: 20,
+                goal_ticks: vec![Some(10), None],
+                finished_tick: None,
+                closest_approaches: Some(vec![0, 1500]),
+                collisions: 3,
+                racer: Racer {
+                    x: 1,
+                    y: 2,
+                    vx: 3,
+                    vy: 4,
+                    radius: 5,
+                },
+            }),
+        ];
+
+        // když přibude varianta, tohle se nezkompiluje, dokud se nepřidá i nahoru
+        for message in &messages {
+            match message {
+                Message::BadTestName(_)
+                | Message::MissingMap
+                | Message::MapError(_)
+                | Message::ScoringError(_)
+                | Message::MissingGraph
+                | Message::GraphError(_)
+                | Message::SettingError(_)
+                | Message::ReadError
+                | Message::FirstLineError
+                | Message::NumberTypeError(_)
+                | Message::NumberCountError(_)
+                | Message::LengthError(_)
+                | Message::MissingInstructionsError(_, _)
+                | Message::ExtraLineError(_)
+                | Message::TooManyInstructionsError(_)
+                | Message::LineLengthError(_)
+                | Message::EncodingError(_)
+                | Message::PathFirstLineError
+                | Message::VertexTypeError(_)
+                | Message::VertexCountError(_)
+                | Message::MissingVerticesError(_, _)
+                | Message::PathExtraLineError(_)
+                | Message::TooManyVerticesError(_)
+                | Message::VertexOutOfBounds(_)
+                | Message::VertexBlocked(_, _)
+                | Message::EdgeNotFound(_, _)
+                | Message::UnreachedGoals(_)
+                | Message::Success(_)
+                | Message::Report(_) => {}
+            }
+        }
+
+        messages
+    }
+
+    #[test]
+    fn test_every_message_translated() {
+        let messages = all_messages();
+
+        for language in Language::ALL {
+            for message in &messages {
+                assert!(!message.text(language).is_empty(), "{:?}", message);
+            }
+        }
+
+        for message in &messages {
+            assert_ne!(
+                message.text(Language::Czech),
+                message.text(Language::English),
+                "{:?} is not translated",
+                message
+            );
+        }
+
+        // různé zprávy musí jít od sebe rozeznat
+        for language in Language::ALL {
+            let mut texts = messages
+                .iter()
+                .map(|m| m.text(language))
+                .collect::<Vec<_>>();
+            texts.sort();
+            texts.dedup();
+
+            assert_eq!(texts.len(), messages.len());
+        }
+    }
+
+    #[test]
+    fn test_marathon_messages_fit() {
+        // nic nedosaženo, velké souřadnice i vzdálenosti
+        let goals = 70;
+        let report = Report {
+            ticks: 1_000_000,
+            goal_ticks: vec![None; goals],
+            finished_tick: None,
+            closest_approaches: Some(vec![1_234_567; goals]),
+            collisions: 1_000_000,
+            racer: Racer {
+                x: -1_200_000,
+                y: -1_200_000,
+                vx: -12_345,
+                vy: -12_345,
+                radius: 1000,
+            },
+        };
+
+        for language in Language::ALL {
+            let message = format!(
+                "{}\n{}",
+                Message::UnreachedGoals((1..=goals).collect()).text(language),
+                Message::Report(report.clone()).text(language)
+            );
+
+            assert!(message.len() <= MESSAGE_LIMIT, "{}", message);
+            assert!(message.contains("70"));
+        }
+    }
+
+    #[test]
+    fn test_every_read_error_has_message() {
+        let errors = [
+            OutputReadError::IoError(std::io::Error::other("broken pipe")),
+            OutputReadError::FirstLineError,
+            OutputReadError::NumberTypeError(2),
+            OutputReadError::NumberCountError(2),
+            OutputReadError::LengthError(2),
+            OutputReadError::MissingInstructionsError(2, 1),
+            OutputReadError::ExtraLineError(2),
+            OutputReadError::TooManyInstructionsError(1000),
+            OutputReadError::LineLengthError(2),
+            OutputReadError::EncodingError(2),
+        ];
+
+        for error in &errors {
+            for language in Language::ALL {
+                assert!(!Message::from(error).text(language).is_empty());
+            }
+        }
+
+        // cesta nemá instrukce, jen vrcholy
+        for error in errors {
+            let instructions = Message::from(&error);
+            let path = Message::from(&PathError::Read(error));
+
+            match instructions {
+                Message::ReadError
+                | Message::LengthError(_)
+                | Message::LineLengthError(_)
+                | Message::EncodingError(_) => assert_eq!(path, instructions),
+                _ => {
+                    assert_ne!(path, instructions);
+                    assert!(!path.text(Language::English).contains("instruction"));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_language_from_code() {
+        assert_eq!(Language::from_code("en"), Some(Language::English));
+        assert_eq!(Language::from_code(" CS "), Some(Language::Czech));
+        assert_eq!(Language::from_code("de"), None);
+    }
+}
@@ -0,0 +1,1350 @@
+/// Naprosto shamelessly ukradeno/poupraveno z 34-2-4 (díky Jirko)
+mod graph;
+mod messages;
+mod report;
+
+use crate::generator;
+use crate::opendata::judge::{Verdict, MESSAGE_LIMIT};
+use crate::opendata::JudgeContext;
+use crate::simulation::*;
+use crate::solve;
+use std::env;
+use std::fs::{self, File};
+use std::io::{BufRead, Read};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+pub use messages::{Language, Message};
+pub use report::Report;
+
+/// Proměnná prostředí, která zapne podrobný průběh letu ve zprávě.
+pub const REPORT_VAR: &str = "JUDGE_REPORT";
+
+/// Proměnná prostředí s maximálním počtem instrukcí.
+pub const MAX_INSTRUCTIONS_VAR: &str = "JUDGE_MAX_INSTRUCTIONS";
+
+/// Výchozí maximální počet instrukcí; i maraton jde dolétat na mnohem míň.
+pub const MAX_INSTRUCTIONS: usize = 1_000_000;
+
+/// Nastavení judge, která nezávisí na mapě.
+#[derive(Debug, Clone, Copy)]
+pub struct JudgeOptions {
+    pub language: Language,
+    pub report: bool, // přidat ke zprávě podrobný průběh letu (viz [`Report`])
+    pub max_instructions: usize,
+}
+
+impl Default for JudgeOptions {
+    fn default() -> Self {
+        Self {
+            language: Language::default(),
+            report: false,
+            max_instructions: MAX_INSTRUCTIONS,
+        }
+    }
+}
+
+impl JudgeOptions {
+    /// Nastavení z proměnných prostředí. Chybné nastavení je chyba judge, ne řešitele, takže
+    /// se nesmí potichu nahradit výchozím.
+    pub fn from_env() -> Result<Self, Message> {
+        Ok(Self {
+            language: Language::from_env(),
+            report: env::var(REPORT_VAR).is_ok_and(|value| matches!(value.as_str(), "1" | "true")),
+            max_instructions: max_instructions(env::var(MAX_INSTRUCTIONS_VAR))?,
+        })
+    }
+}
+
+/// Maximální počet instrukcí z hodnoty [`MAX_INSTRUCTIONS_VAR`].
+fn max_instructions(value: Result<String, env::VarError>) -> Result<usize, Message> {
+    match value {
+        Err(env::VarError::NotPresent) => Ok(MAX_INSTRUCTIONS),
+        Ok(value) => (value.trim().parse())
+            .map_err(|_| Message::SettingError(format!("{}={}", MAX_INSTRUCTIONS_VAR, value))),
+        Err(e) => Err(Message::SettingError(format!(
+            "{}: {}",
+            MAX_INSTRUCTIONS_VAR, e
+        ))),
+    }
+}
+
+pub fn judge(mut context: JudgeContext) -> Verdict {
+    let options = match JudgeOptions::from_env() {
+        Ok(options) => options,
+        Err(message) => {
+            return Verdict::internal_error().message(&message.text(Language::from_env()))
+        }
+    };
+    let language = options.language;
+
+    let input_file = context.input_file.take();
+    let dataset_dir = context.dataset_dir.as_deref();
+
+    let simulation = match load_map(&context.test_name, input_file, dataset_dir) {
+        Ok(simulation) => simulation,
+        Err(message) => return Verdict::internal_error().message(&message.text(language)),
+    };
+
+    let scoring = match load_scoring(&context) {
+        Ok(scoring) => scoring,
+        Err(e) => {
+            return Verdict::internal_error().message(&Message::ScoringError(e).text(language));
+        }
+    };
+
+    match scoring.mode {
+        Mode::Instructions => judge_submission(simulation, &scoring, context.submission, options),
+        Mode::Graph => {
+            let graph = match load_graph(&context) {
+                Ok(graph) => graph,
+                Err(message) => return Verdict::internal_error().message(&message.text(language)),
+            };
+
+            graph::judge_path(
+                &simulation.map,
+                &graph,
+                &scoring,
+                context.submission,
+                options,
+            )
+        }
+    }
+}
+
+/// Jméno mapy testu. Generované testy mají místo jména číslo (viz [`generator::TESTS`]),
+/// `1` je tedy `sprint` a `2` je `marathon`; ostatní testy se jmenují stejně jako mapa.
+fn map_name(test_name: &str) -> &str {
+    match test_name.parse::<usize>() {
+        Ok(test) if (1..=generator::TESTS.len()).contains(&test) => {
+            generator::TESTS[test - 1].name()
+        }
+        _ => test_name,
+    }
+}
+
+/// Cesta k mapě v adresáři s datasety, pro jméno testu (`sprint`, `marathon`, `1`, ...).
+fn map_path(dir: &Path, test_name: &str) -> Option<PathBuf> {
+    let test_name = map_name(test_name);
+
+    // jméno testu nesmí utéct z adresáře
+    if test_name.is_empty() || test_name.contains(['/', '\\']) || test_name.starts_with('.') {
+        return None;
+    }
+
+    Some(dir.join(format!("{}.txt", test_name)))
+}
+
+fn load_map(
+    test_name: &str,
+    input_file: Option<File>,
+    dataset_dir: Option<&str>,
+) -> Result<Simulation, Message> {
+    // vstup od opendat má přednost, je to přesně ta mapa, kterou dostali řešitelé
+    let result = match (input_file, dataset_dir) {
+        (Some(file), _) => Simulation::from_reader(file),
+        (None, Some(dir)) => match map_path(Path::new(dir), test_name) {
+            Some(path) => Simulation::load(&path),
+            None => return Err(Message::BadTestName(test_name.to_string())),
+        },
+        (None, None) => return Err(Message::MissingMap),
+    };
+
+    result.map_err(|e| Message::MapError(e.to_string()))
+}
+
+/// Cesty, kde může ležet mapa: vstup (to jsou např. ukázkové vstupy v `maps/`) a mapa
+/// v adresáři s datasety podle jména (viz [`map_name`]).
+fn map_paths(context: &JudgeContext) -> impl Iterator<Item = PathBuf> + '_ {
+    let input = context.input_filename.as_ref().map(PathBuf::from);
+    let in_dataset =
+        (context.dataset_dir.as_ref()).and_then(|dir| map_path(Path::new(dir), &context.test_name));
+
+    input.into_iter().chain(in_dataset)
+}
+
+/// Soubor k mapě (bodování) se stejným jménem a jinou příponou. Hledá se nejdřív vedle
+/// vstupu, potom vedle mapy v adresáři s datasety, takže test `1` má bodování
+/// v `<DATASET_DIR>/sprint.scoring`.
+fn sibling_path(context: &JudgeContext, extension: &str) -> Option<PathBuf> {
+    map_paths(context)
+        .map(|path| path.with_extension(extension))
+        .find(|path| path.exists())
+}
+
+/// Graf asteroidů k mapě se jmenuje podle mapy, stejně jako bodování (viz [`map_name`]), a leží
+/// v adresáři `graphs` vedle adresáře se vstupem nebo s datasety (jako `maps/` a `graphs/`
+/// v repozitáři), takže graf testu `1` je `<DATASET_DIR>/../graphs/sprint.txt`.
+fn graph_path(context: &JudgeContext) -> Option<PathBuf> {
+    let input_dir = (context.input_filename.as_ref())
+        .and_then(|path| Some(Path::new(path).parent()?.to_path_buf()));
+    let dataset_dir = context.dataset_dir.as_ref().map(PathBuf::from);
+
+    (input_dir.into_iter().chain(dataset_dir))
+        .filter_map(|dir| map_path(&dir.join("..").join("graphs"), &context.test_name))
+        .find(|path| path.exists())
+}
+
+fn load_scoring(context: &JudgeContext) -> Result<Scoring, String> {
+    match sibling_path(context, "scoring") {
+        Some(path) => fs::read_to_string(&path)
+            .map_err(|e| e.to_string())?
+            .parse(),
+        // když soubor není, použijeme výchozí bodování
+        None => Ok(Scoring::for_test(&context.test_name)),
+    }
+}
+
+fn load_graph(context: &JudgeContext) -> Result<solve::AsteroidGraph, Message> {
+    let path = graph_path(context).ok_or(Message::MissingGraph)?;
+
+    solve::load_asteroid_graph(&path)
+        .map_err(|e| Message::GraphError(format!("{}: {}", path.display(), e)))
+}
+
+/// Ohodnotí instrukce přečtené z `reader` na dané mapě.
+fn judge_submission<TReader: BufRead>(
+    mut simulation: Simulation,
+    scoring: &Scoring,
+    reader: TReader,
+    options: JudgeOptions,
+) -> Verdict {
+    let language = options.language;
+
+    let map = simulation.map.clone();
+
+    simulation.restart();
+
+    // vzdálenosti od cílů stojí O(cílů) za tik, sledujeme je jen pro výpis průběhu
+    let mut report = if options.report {
+        Report::detailed(&simulation)
+    } else {
+        Report::new(&simulation)
+    };
+
+    // simulujeme rovnou během čtení, takže instrukce nikde neskladujeme
+    let result = SubmissionReader::new(reader, options.max_instructions).and_then(|submission| {
+        for instruction in submission.instructions(&map.physics) {
+            // brrrrrrr
+            report.tick(&mut simulation, instruction?);
+        }
+
+        Ok(())
+    });
+
+    match result {
+        Ok(()) => {}
+        Err(ref e @ OutputReadError::IoError(ref error)) => {
+            // podrobnosti jsou jen v JSON verdiktu, soutěžící dostane obecnou zprávu
+            return Verdict::internal_error()
+                .message(&Message::from(e).text(language))
+                .detail("read_error", error.to_string());
+        }
+        Err(e) => return Verdict::wrong().message(&Message::from(&e).text(language)),
+    }
+
+    let (verdict, message) = if let Some(finished_tick) = report.finished_tick {
+        let points = scoring.points(match scoring.length {
+            Length::Finished => finished_tick,
+            Length::Submitted => report.ticks,
+        });
+
+        (
+            Verdict::correct().override_points(points),
+            Message::Success(points),
+        )
+    } else {
+        let message = Message::UnreachedGoals(report.unreached_goals());
+        let points = scoring.partial_points(report.reached_goals(), report.goal_ticks.len());
+
+        // i částečné body jde dát jen správnému řešení
+        match points {
+            Some(points) if points > 0.0 => (Verdict::correct().override_points(points), message),
+            _ => (Verdict::wrong(), message),
+        }
+    };
+
+    // podrobnosti pro strojové zpracování (viz `VERDICT_FORMAT`), řešitelé je nevidí
+    let verdict = verdict
+        .detail("mode", "instructions")
+        .detail("ticks", report.ticks)
+        .detail("finished_tick", report.finished_tick)
+        .detail("goals", report.goal_ticks.len())
+        .detail("goals_reached", report.reached_goals())
+        .detail("goal_ticks", report.goal_ticks.clone())
+        .detail("collisions", report.collisions);
+
+    let mut message = message.text(language);
+
+    if options.report {
+        message += "\n";
+        message += &Message::Report(report).text(language);
+    }
+
+    verdict.message(&truncate_message(message))
+}
+
+/// Zkrátí zprávu na [`MESSAGE_LIMIT`] bajtů, delší by se v odevzdávátku nevešla.
+fn truncate_message(mut message: String) -> String {
+    if message.len() > MESSAGE_LIMIT {
+        let mut end = MESSAGE_LIMIT - '…'.len_utf8();
+
+        while !message.is_char_boundary(end) {
+            end -= 1;
+        }
+
+        message.truncate(end);
+        message.push('…');
+    }
+
+    message
+}
+
+/// Jak rychle klesají body s délkou řešení delšího než baseline.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Curve {
+    Hyperbolic,  // baseline / (délka - baseline)
+    Linear,      // nula bodů při dvojnásobku baseline
+    Logarithmic, // max / (1 + ln(délka / baseline)), nikdy neklesne na nulu
+}
+
+/// Co řešitelé odevzdávají.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    Instructions, // instrukce pro závodníka
+    Graph,        // cestu po grafu asteroidů (viz [`graph_path`])
+}
+
+/// Co se počítá jako délka řešení (u grafu je to délka cesty).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Length {
+    Finished,  // počet tiků do dosažení posledního cíle, instrukce za ním se nepočítají
+    Submitted, // počet odevzdaných instrukcí
+}
+
+/// Bodování jedné mapy.
+///
+/// Soubor s bodováním (`<mapa>.scoring`, kde se hledá viz [`sibling_path`]) má na každém řádku `klíč=hodnota`, např.
+/// ```text
+/// mode=instructions
+/// max_points=12
+/// baseline=1151
+/// curve=hyperbolic
+/// length=finished
+/// partial_points=false
+/// ```
+/// Chybějící klíče mají výchozí hodnoty, prázdné řádky a řádky začínající `#` se přeskakují.
+/// Jen `mode=graph` musí mít `baseline`, a to délku cesty (ne počet tiků).
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Scoring {
+    mode: Mode,
+    max_points: f64,
+    baseline: Option<usize>, // délka dobrého řešení; bez ní stačí mapu dolétat
+    curve: Curve,
+    length: Length,
+    partial_points: bool, // body úměrné počtu dosažených cílů, i když nejsou všechny
+}
+
+impl Default for Scoring {
+    fn default() -> Self {
+        Self {
+            mode: Mode::Instructions,
+            max_points: 12.0,
+            baseline: None,
+            curve: Curve::Hyperbolic,
+            length: Length::Finished,
+            partial_points: false,
+        }
+    }
+}
+
+impl Scoring {
+    /// Výchozí bodování pro mapy, které soubor s bodováním nemají.
+    fn for_test(test_name: &str) -> Self {
+        // tyhle hodnoty jsou hodné dobré baseline řešení obou úložek
+        // pokud někdo dosáhne těch, tak max body, jinak exponenciálně klesá skóre
+        let baseline = match map_name(test_name) {
+            "sprint" => Some(1151),
+            "marathon" => Some(14207),
+            // pro ostatní mapy baseline nemáme, stačí je dolétat
+            _ => None,
+        };
+
+        Self {
+            baseline,
+            ..Self::default()
+        }
+    }
+
+    fn points(&self, length: usize) -> f64 {
+        let baseline = match self.baseline {
+            Some(baseline) if length > baseline => baseline as f64,
+            _ => return self.max_points,
+        };
+
+        let length = length as f64;
+
+        let points = match self.curve {
+            Curve::Hyperbolic => baseline / (length - baseline),
+            Curve::Linear => self.max_points * (2.0 - length / baseline),
+            Curve::Logarithmic => self.max_points / (1.0 + (length / baseline).ln()),
+        };
+
+        // hyperbola je těsně za baseline nad maximem
+        points.clamp(0.0, self.max_points)
+    }
+
+    /// Body za nedokončený let, pokud je mapa dává.
+    fn partial_points(&self, reached_goals: usize, goals: usize) -> Option<f64> {
+        (self.partial_points && goals > 0)
+            .then(|| self.max_points * reached_goals as f64 / goals as f64)
+    }
+}
+
+impl FromStr for Scoring {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut scoring = Self::default();
+
+        for line in s.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (key, value) = line.split_once('=').ok_or(line)?;
+            let (key, value) = (key.trim(), value.trim());
+
+            match key {
+                "mode" => {
+                    scoring.mode = match value {
+                        "instructions" => Mode::Instructions,
+                        "graph" => Mode::Graph,
+                        _ => return Err(key.to_string()),
+                    }
+                }
+                "max_points" => {
+                    scoring.max_points = value
+                        .parse()
+                        .ok()
+                        .filter(|&v: &f64| v.is_finite() && v >= 0.0)
+                        .ok_or(key)?
+                }
+                "baseline" => {
+                    scoring.baseline = Some(value.parse().ok().filter(|&v| v > 0).ok_or(key)?)
+                }
+                "curve" => {
+                    scoring.curve = match value {
+                        "hyperbolic" => Curve::Hyperbolic,
+                        "linear" => Curve::Linear,
+                        "logarithmic" => Curve::Logarithmic,
+                        _ => return Err(key.to_string()),
+                    }
+                }
+                "length" => {
+                    scoring.length = match value {
+                        "finished" => Length::Finished,
+                        "submitted" => Length::Submitted,
+                        _ => return Err(key.to_string()),
+                    }
+                }
+                "partial_points" => scoring.partial_points = value.parse().ok().ok_or(key)?,
+                _ => return Err(key.to_string()),
+            }
+        }
+
+        if scoring.mode == Mode::Graph && scoring.baseline.is_none() {
+            return Err("baseline".to_string());
+        }
+
+        Ok(scoring)
+    }
+}
+
+/// Chyby v odevzdaném výstupu; čísla řádků jsou řádky souboru (od jedničky, první je počet).
+#[derive(Debug)]
+#[allow(clippy::enum_variant_names)]
+enum OutputReadError {
+    IoError(std::io::Error),
+    FirstLineError,
+    NumberTypeError(usize),                 // čísla nejsou i8
+    NumberCountError(usize),                // nemáme x and y (máme víc/míň čísel)
+    LengthError(usize),                     // instrukce není normalizovaná (viz zadání)
+    MissingInstructionsError(usize, usize), // očekávaný a skutečný počet instrukcí
+    ExtraLineError(usize),                  // za poslední instrukcí je ještě neprázdný řádek
+    TooManyInstructionsError(usize),        // instrukcí je víc, než kolik povolujeme
+    LineLengthError(usize),                 // řádek je podezřele dlouhý
+    EncodingError(usize),                   // řádek není v UTF-8
+}
+
+impl From<std::io::Error> for OutputReadError {
+    fn from(e: std::io::Error) -> Self {
+        OutputReadError::IoError(e)
+    }
+}
+
+/// Nejdelší řádek, který ještě čteme; instrukce se vejde do pár bajtů.
+const MAX_LINE_LENGTH: usize = 1024;
+
+/// Kolik prázdných řádků ještě snese konec výstupu; další už jsou něco navíc.
+const MAX_TRAILING_LINES: usize = 100;
+
+const BOM: &[u8] = "\u{feff}".as_bytes();
+
+/// Čte odevzdaný výstup postupně po řádcích, aby se nemusel celý držet v paměti.
+///
+/// Počet řádků (instrukcí nebo vrcholů) z prvního řádku se přečte hned při vytvoření.
+///
+/// Co tolerujeme, protože to tam dávají editory a operační systémy:
+/// - BOM na začátku souboru,
+/// - konce řádků `\r\n`,
+/// - mezery a tabulátory okolo čísel,
+/// - prázdné řádky za posledním řádkem (včetně chybějícího konce posledního řádku).
+///
+/// Čísla instrukcí se parsují jako v Rustu, takže projde i `-0` nebo `+5`, ale ne `1.0`
+/// nebo `0x1`. Počet na prvním řádku je jen z číslic, znaménko (ani `+0`, ani `-0`) mít nesmí.
+struct SubmissionReader<TReader: BufRead> {
+    reader: TReader,
+    buffer: Vec<u8>,
+    line_number: usize, // číslo posledního přečteného řádku
+    length: usize,
+    read: usize, // kolik řádků (bez prvního) už jsme přečetli
+}
+
+impl<TReader: BufRead> SubmissionReader<TReader> {
+    fn new(reader: TReader, max_length: usize) -> Result<Self, OutputReadError> {
+        let mut submission = Self {
+            reader,
+            buffer: Vec::new(),
+            line_number: 0,
+            length: 0,
+            read: 0,
+        };
+
+        let line = match submission.read_line()? {
+            Some(line) => line,
+            None => return Err(OutputReadError::FirstLineError),
+        };
+
+        let line = line.trim();
+
+        // `usize` by vzal i `+0`, ale `-0` ne; znaménko u počtu prostě nebereme vůbec
+        if !line.bytes().all(|byte| byte.is_ascii_digit()) {
+            return Err(OutputReadError::FirstLineError);
+        }
+
+        submission.length = match line.parse::<usize>() {
+            Ok(len) => len,
+            Err(_) => return Err(OutputReadError::FirstLineError),
+        };
+
+        // ani to nezačneme číst
+        if submission.length > max_length {
+            return Err(OutputReadError::TooManyInstructionsError(max_length));
+        }
+
+        Ok(submission)
+    }
+
+    /// Přečte další řádek (bez konce řádku a mezer na konci), vrátí `None` na konci vstupu.
+    fn read_line(&mut self) -> Result<Option<&str>, OutputReadError> {
+        self.buffer.clear();
+
+        // nikdy nepřečteme víc než MAX_LINE_LENGTH, ani když v souboru žádný konec řádku není
+        let limit = MAX_LINE_LENGTH as u64 + 1;
+        if (&mut self.reader)
+            .take(limit)
+            .read_until(b'\n', &mut self.buffer)?
+            == 0
+        {
+            return Ok(None);
+        }
+
+        self.line_number += 1;
+
+        if self.buffer.ends_with(b"\n") {
+            self.buffer.pop();
+        } else if self.buffer.len() > MAX_LINE_LENGTH {
+            return Err(OutputReadError::LineLengthError(self.line_number));
+        }
+
+        let mut line = self.buffer.as_slice();
+
+        if self.line_number == 1 {
+            line = line.strip_prefix(BOM).unwrap_or(line);
+        }
+
+        match std::str::from_utf8(line) {
+            // trim_end se postará i o \r
+            Ok(line) => Ok(Some(line.trim_end())),
+            Err(_) => Err(OutputReadError::EncodingError(self.line_number)),
+        }
+    }
+
+    /// Další řádek s číslem řádku; `None`, když už jsou přečtené všechny ohlášené řádky.
+    fn next_line(&mut self) -> Result<Option<(usize, &str)>, OutputReadError> {
+        if self.read == self.length {
+            // za posledním řádkem smí být už jen pár prázdných řádků, jinak bychom jich mohli
+            // číst neomezeně
+            for _ in 0..=MAX_TRAILING_LINES {
+                match self.read_line()? {
+                    Some("") => {}
+                    Some(_) => return Err(OutputReadError::ExtraLineError(self.line_number)),
+                    None => return Ok(None),
+                }
+            }
+
+            return Err(OutputReadError::ExtraLineError(self.line_number));
+        }
+
+        let (length, read) = (self.length, self.read);
+        let line_number = self.line_number + 1;
+        self.read += 1;
+
+        match self.read_line()? {
+            Some(line) => Ok(Some((line_number, line))),
+            None => Err(OutputReadError::MissingInstructionsError(length, read)),
+        }
+    }
+
+    /// Řádek rozdělený na přesně `N` čísel.
+    fn next_numbers<T: FromStr + Copy + Default, const N: usize>(
+        &mut self,
+    ) -> Result<Option<(usize, [T; N])>, OutputReadError> {
+        let (line_number, line) = match self.next_line()? {
+            Some(line) => line,
+            None => return Ok(None),
+        };
+
+        let parts = line.split_whitespace().collect::<Vec<&str>>();
+
+        if parts.len() != N {
+            return Err(OutputReadError::NumberCountError(line_number));
+        }
+
+        let mut parsed = [T::default(); N];
+        for (value, part) in parsed.iter_mut().zip(parts) {
+            *value = match part.parse::<T>() {
+                Ok(number) => number,
+                Err(_) => return Err(OutputReadError::NumberTypeError(line_number)),
+            };
+        }
+
+        Ok(Some((line_number, parsed)))
+    }
+
+    fn next_instruction(
+        &mut self,
+        physics: &PhysicsConfig,
+    ) -> Result<Option<Instruction>, OutputReadError> {
+        let (line_number, [vx, vy]) = match self.next_numbers::<InstType, 2>()? {
+            Some(numbers) => numbers,
+            None => return Ok(None),
+        };
+
+        let instruction = Instruction { vx, vy };
+
+        // mapa může mít jiné maximální zrychlení než výchozí
+        if !physics.allows(instruction) {
+            return Err(OutputReadError::LengthError(line_number));
+        }
+
+        Ok(Some(instruction))
+    }
+
+    /// Iterátor přes instrukce, který skončí po první chybě.
+    fn instructions(self, physics: &PhysicsConfig) -> Instructions<'_, TReader> {
+        Instructions {
+            submission: self,
+            physics,
+            failed: false,
+        }
+    }
+}
+
+struct Instructions<'a, TReader: BufRead> {
+    submission: SubmissionReader<TReader>,
+    physics: &'a PhysicsConfig,
+    failed: bool,
+}
+
+impl<TReader: BufRead> Iterator for Instructions<'_, TReader> {
+    type Item = Result<Instruction, OutputReadError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+
+        let result = self.submission.next_instruction(self.physics);
+        self.failed = result.is_err();
+
+        result.transpose()
+    }
+}
+
+/// Přečte celý odevzdaný výstup najednou.
+#[allow(dead_code)] // soudce čte výstup postupně
+fn read_submitted_output<TReader: BufRead>(
+    reader: TReader,
+    physics: &PhysicsConfig,
+) -> Result<Vec<Instruction>, OutputReadError> {
+    SubmissionReader::new(reader, usize::MAX)?
+        .instructions(physics)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::opendata::judge::{Detail, VerdictType};
+    use rand::prelude::*;
+    use rand::rngs::StdRng;
+    use std::fs;
+    use std::io::{self, BufReader};
+
+    #[test]
+    fn test_reader_streams() {
+        let physics = PhysicsConfig::default();
+
+        // an endless submission, so anything buffering it all would never finish
+        let endless = |start: &'static str| start.as_bytes().chain(io::repeat(b'\n'));
+
+        let first_error = |reader, max_instructions| match SubmissionReader::new(
+            BufReader::new(reader),
+            max_instructions,
+        ) {
+            Ok(submission) => submission.instructions(&physics).find_map(Result::err),
+            Err(e) => Some(e),
+        };
+
+        assert!(matches!(
+            first_error(endless("1000000000\n"), 1000),
+            Some(OutputReadError::TooManyInstructionsError(1000))
+        ));
+        assert!(matches!(
+            first_error(endless("1000000000\n1 1\nx 1\n"), usize::MAX),
+            Some(OutputReadError::NumberTypeError(3))
+        ));
+        assert!(matches!(
+            first_error(endless("3\n1 1\n"), usize::MAX),
+            Some(OutputReadError::NumberCountError(3))
+        ));
+
+        // a single line that never ends
+        let long_line = "2\n1 1\n".as_bytes().chain(io::repeat(b' '));
+        assert!(matches!(
+            first_error(long_line, usize::MAX),
+            Some(OutputReadError::LineLengthError(3))
+        ));
+
+        // endless empty lines after the last instruction
+        let blank_lines = "1\n1 1\n".as_bytes().chain(io::repeat(b'\n'));
+        assert!(matches!(
+            first_error(blank_lines, usize::MAX),
+            Some(OutputReadError::ExtraLineError(line)) if line == MAX_TRAILING_LINES + 3
+        ));
+
+        // a few of them are fine
+        let blank_lines = format!("1\n1 1\n{}", "\n".repeat(MAX_TRAILING_LINES));
+        assert!(read_submitted_output(blank_lines.as_bytes(), &physics).is_ok());
+
+        // the count from the first line is exact
+        assert!(matches!(
+            read_submitted_output("1\n1 1\n1 1\n".as_bytes(), &physics),
+            Err(OutputReadError::ExtraLineError(3))
+        ));
+        assert!(matches!(
+            read_submitted_output("2\n1 1\n".as_bytes(), &physics),
+            Err(OutputReadError::MissingInstructionsError(2, 1))
+        ));
+        assert!(matches!(
+            read_submitted_output("2\n1 1\n-1 -1".as_bytes(), &physics),
+            Ok(instructions) if instructions.len() == 2
+        ));
+    }
+
+    /// Výsledek čtení jako řetězec, aby šly výsledky snadno porovnávat.
+    fn read(input: &[u8]) -> Result<Vec<(InstType, InstType)>, String> {
+        match read_submitted_output(input, &PhysicsConfig::default()) {
+            Ok(instructions) => Ok(instructions.iter().map(|i| (i.vx, i.vy)).collect()),
+            Err(e) => Err(format!("{:?}", e)),
+        }
+    }
+
+    #[test]
+    fn test_reader_corpus() {
+        let ok = Ok(vec![(1, 1), (-1, -1)]);
+        let err = |e: &str| Err(e.to_string());
+
+        let corpus: &[(&[u8], Result<_, String>)] = &[
+            (b"2\n1 1\n-1 -1\n", ok.clone()),
+            (b"2\n1 1\n-1 -1", ok.clone()),
+            (b"2\r\n1 1\r\n-1 -1\r\n", ok.clone()),
+            (b"\xef\xbb\xbf2\n1 1\n-1 -1\n", ok.clone()),
+            (b"2 \n 1\t1  \n-1 -1\t\n", ok.clone()),
+            (b"2\n1 1\n-1 -1\n\n  \r\n\n", ok.clone()),
+            (b"2\n-0 +0\n0 -0\n", Ok(vec![(0, 0), (0, 0)])),
+            (b"0\n", Ok(vec![])),
+            (b"0", Ok(vec![])),
+            (b"", err("FirstLineError")),
+            (b"\n1 1\n", err("FirstLineError")),
+            (b"-0\n", err("FirstLineError")),
+            (b"+0\n", err("FirstLineError")),
+            (b"+2\n1 1\n-1 -1\n", err("FirstLineError")),
+            (b"2 instructions\n1 1\n-1 -1\n", err("FirstLineError")),
+            (b"\xef\xbb\xbf\xef\xbb\xbf1\n1 1\n", err("FirstLineError")),
+            (b"1\n\xef\xbb\xbf1 1\n", err("NumberTypeError(2)")),
+            (b"2\n1 1\n-1 -1\n1 1\n", err("ExtraLineError(4)")),
+            (b"2\n1 1\n-1 -1\n\n\n0\n", err("ExtraLineError(6)")),
+            (b"2\n1 1\n\n-1 -1\n", err("NumberCountError(3)")),
+            (b"2\r\n1 1\r\n\r\n", err("NumberCountError(3)")),
+            (b"2\n1 1\n1 1 1\n", err("NumberCountError(3)")),
+            (b"2\r\n1 1\r\nx 1\r\n", err("NumberTypeError(3)")),
+            (b"2\n1 1\n1.0 1\n", err("NumberTypeError(3)")),
+            (b"2\n1 1\n0x1 1\n", err("NumberTypeError(3)")),
+            (b"2\n1 1\n--1 1\n", err("NumberTypeError(3)")),
+            (b"2\n1 1\n128 0\n", err("NumberTypeError(3)")),
+            (b"2\n1 1\n127 127\n", err("LengthError(3)")),
+            (b"2\n1 1\n", err("MissingInstructionsError(2, 1)")),
+            (b"2\n1 1\n\xff 1\n", err("EncodingError(3)")),
+            (b"\xff\n", err("EncodingError(1)")),
+        ];
+
+        for (input, expected) in corpus {
+            assert_eq!(
+                &read(input),
+                expected,
+                "{:?}",
+                String::from_utf8_lossy(input)
+            );
+        }
+    }
+
+    #[test]
+    fn test_reader_fuzz() {
+        let mut rng = StdRng::seed_from_u64(0);
+
+        let valid = b"3\n1 1\n-5 7\n0 0\n";
+        let alphabet = b"0123456789 -+\r\n\tx\xef\xbb\xbf";
+
+        for _ in 0..10_000 {
+            let mut input = valid.to_vec();
+
+            for _ in 0..rng.random_range(1..4) {
+                let i = rng.random_range(0..=input.len());
+                let byte = *alphabet.choose(&mut rng).unwrap();
+
+                match rng.random_range(0..3) {
+                    0 => input.insert(i, byte),
+                    1 if i < input.len() => drop(input.remove(i)),
+                    _ if i < input.len() => input[i] = byte,
+                    _ => {}
+                }
+            }
+
+            let lines = input.split(|&b| b == b'\n').count();
+
+            match read_submitted_output(input.as_slice(), &PhysicsConfig::default()) {
+                Ok(instructions) => {
+                    let count = String::from_utf8_lossy(&input)
+                        .lines()
+                        .next()
+                        .unwrap()
+                        .to_string();
+                    let count = count.trim_start_matches('\u{feff}').trim();
+
+                    assert_eq!(count.parse::<usize>().unwrap(), instructions.len());
+                }
+                Err(
+                    OutputReadError::NumberTypeError(line)
+                    | OutputReadError::NumberCountError(line)
+                    | OutputReadError::LengthError(line)
+                    | OutputReadError::ExtraLineError(line),
+                ) => assert!(2 <= line && line <= lines, "{:?}", input),
+                Err(OutputReadError::EncodingError(line)) => assert!(line <= lines),
+                Err(OutputReadError::MissingInstructionsError(expected, actual)) => {
+                    assert!(actual < expected)
+                }
+                Err(OutputReadError::FirstLineError) => {}
+                Err(e) => panic!("{:?} for {:?}", e, input),
+            }
+        }
+    }
+
+    #[test]
+    fn test_max_instructions() {
+        let map = fs::read_to_string("../../test/solves/test.txt").unwrap();
+        let simulation = Simulation::from_str(&map).unwrap();
+        let solve = fs::read_to_string("../../test/solves/test.in").unwrap();
+
+        let length = solve.lines().count() - 1;
+
+        for (max_instructions, verdict_type) in [
+            (length, VerdictType::Correct),
+            (length - 1, VerdictType::Wrong),
+        ] {
+            let options = JudgeOptions {
+                max_instructions,
+                ..JudgeOptions::default()
+            };
+            let verdict = judge_submission(
+                simulation.clone(),
+                &Scoring::default(),
+                solve.as_bytes(),
+                options,
+            );
+
+            assert_eq!(verdict.verdict_type(), verdict_type);
+        }
+    }
+
+    #[test]
+    fn test_max_instructions_setting() {
+        assert_eq!(
+            max_instructions(Err(env::VarError::NotPresent)),
+            Ok(MAX_INSTRUCTIONS)
+        );
+        assert_eq!(max_instructions(Ok("1000".to_string())), Ok(1000));
+        assert_eq!(max_instructions(Ok(" 1000\n".to_string())), Ok(1000));
+
+        for value in ["", "-1", "1e6", "lots"] {
+            assert_eq!(
+                max_instructions(Ok(value.to_string())),
+                Err(Message::SettingError(format!(
+                    "{}={}",
+                    MAX_INSTRUCTIONS_VAR, value
+                )))
+            );
+        }
+    }
+
+    #[test]
+    fn test_map_path() {
+        let dir = Path::new("datasets");
+
+        assert_eq!(map_path(dir, "sprint"), Some(dir.join("sprint.txt")));
+        assert_eq!(map_path(dir, "my-map"), Some(dir.join("my-map.txt")));
+
+        // generované testy mají čísla
+        assert_eq!(map_path(dir, "1"), Some(dir.join("sprint.txt")));
+        assert_eq!(map_path(dir, "2"), Some(dir.join("marathon.txt")));
+        assert_eq!(map_path(dir, "3"), Some(dir.join("3.txt")));
+
+        assert_eq!(map_path(dir, ""), None);
+        assert_eq!(map_path(dir, "../sprint"), None);
+        assert_eq!(map_path(dir, "maps/sprint"), None);
+    }
+
+    #[test]
+    fn test_solves_are_correct() {
+        let mut count = 0;
+
+        for entry in fs::read_dir("../../test/solves/").unwrap().flatten() {
+            let path = entry.path();
+
+            if path.extension().is_none_or(|extension| extension != "in") {
+                continue;
+            }
+
+            let test_name = path.file_stem().unwrap().to_str().unwrap();
+            let map = File::open(path.with_extension("txt")).unwrap();
+            let submission = BufReader::new(File::open(&path).unwrap());
+
+            let simulation = load_map(test_name, Some(map), None).ok().unwrap();
+            let scoring = Scoring::for_test(test_name);
+            let verdict =
+                judge_submission(simulation, &scoring, submission, JudgeOptions::default());
+
+            assert_eq!(
+                verdict.verdict_type(),
+                VerdictType::Correct,
+                "{:?}: {:?}",
+                path,
+                verdict.get_message()
+            );
+            assert!(verdict.get_points().unwrap() > 0.0);
+
+            count += 1;
+        }
+
+        assert!(count > 0);
+    }
+
+    #[test]
+    fn test_judge_context() {
+        let context = |test_name: &str, submission: &'static str| JudgeContext {
+            test_name: test_name.to_string(),
+            seed: None,
+            input_file: None,
+            input_filename: None,
+            reference_output_file: None,
+            dataset_dir: Some("../../maps".to_string()),
+            submission: Box::new(submission.as_bytes()),
+        };
+
+        let submission = include_str!("../../../../test/solves/sprint.in");
+
+        let detail = |verdict: &Verdict, key: &str| {
+            (verdict.get_details().iter())
+                .find(|(name, _)| name == key)
+                .map(|(_, value)| value.clone())
+        };
+
+        let verdict = judge(context("sprint", submission));
+        assert_eq!(verdict.verdict_type(), VerdictType::Correct);
+        assert_eq!(detail(&verdict, "goals_reached"), Some(Detail::Integer(1)));
+
+        let verdict = judge(context("sprint", "1\n0 0\n"));
+        assert_eq!(verdict.verdict_type(), VerdictType::Wrong);
+        assert_eq!(detail(&verdict, "goals_reached"), Some(Detail::Integer(0)));
+        assert_eq!(detail(&verdict, "ticks"), Some(Detail::Integer(1)));
+
+        let verdict = judge(context("../sprint", submission));
+        assert_eq!(verdict.verdict_type(), VerdictType::InternalError);
+    }
+
+    #[test]
+    fn test_judge_generated_test() {
+        // opendata dají vygenerovaný vstup a číslo testu, bodování musí být i tak sprintové
+        let context = JudgeContext {
+            test_name: "1".to_string(),
+            seed: Some(0),
+            input_file: Some(File::open("../../test/solves/sprint.txt").unwrap()),
+            input_filename: None,
+            reference_output_file: None,
+            dataset_dir: None,
+            submission: Box::new(include_str!("../../../../test/solves/sprint.in").as_bytes()),
+        };
+
+        // ukázkové řešení je delší než baseline
+        let verdict = judge(context);
+        assert_eq!(verdict.verdict_type(), VerdictType::Correct);
+        assert!(verdict.get_points().unwrap() < Scoring::default().max_points);
+    }
+
+    #[test]
+    fn test_judge_best_solution() {
+        // nejlepší uložené řešení musí jít načíst i odevzdat
+        let instructions = Instruction::load(&PathBuf::from("../../best.txt")).unwrap();
+        assert!(!instructions.is_empty());
+
+        let context = JudgeContext {
+            test_name: "sprint".to_string(),
+            seed: None,
+            input_file: Some(File::open("../../maps/sprint.txt").unwrap()),
+            input_filename: Some("../../maps/sprint.txt".to_string()),
+            reference_output_file: None,
+            dataset_dir: None,
+            submission: Box::new(BufReader::new(File::open("../../best.txt").unwrap())),
+        };
+
+        assert_eq!(judge(context).verdict_type(), VerdictType::Correct);
+    }
+
+    #[test]
+    fn test_unfinished_is_wrong() {
+        let map = File::open("../../test/solves/test.txt").unwrap();
+        let simulation = load_map("test", Some(map), None).ok().unwrap();
+
+        let scoring = Scoring::default();
+
+        for language in Language::ALL {
+            let options = JudgeOptions {
+                language,
+                ..JudgeOptions::default()
+            };
+
+            let verdict =
+                judge_submission(simulation.clone(), &scoring, "1\n0 0\n".as_bytes(), options);
+
+            assert_eq!(verdict.verdict_type(), VerdictType::Wrong);
+            assert_eq!(
+                verdict.get_message(),
+                Some(
+                    Message::UnreachedGoals(vec![1, 2, 3, 4])
+                        .text(language)
+                        .as_str()
+                )
+            );
+        }
+    }
+
+    #[test]
+    fn test_read_honors_physics() {
+        let output = "2\n100 0\n0 -127\n";
+
+        assert!(read_submitted_output(output.as_bytes(), &PhysicsConfig::default()).is_ok());
+
+        let physics = PhysicsConfig {
+            max_acceleration: 100,
+            ..PhysicsConfig::default()
+        };
+
+        assert!(matches!(
+            read_submitted_output(output.as_bytes(), &physics),
+            Err(OutputReadError::LengthError(3))
+        ));
+    }
+
+    #[test]
+    fn test_default_scoring() {
+        let sprint = Scoring::for_test("sprint");
+
+        assert_eq!(sprint.points(1000), 12.0);
+        assert_eq!(sprint.points(1151), 12.0);
+        assert_eq!(sprint.points(1151 * 2), 1.0);
+
+        // the hyperbola would give way more than the maximum right after the baseline
+        assert_eq!(sprint.points(1152), 12.0);
+
+        assert_eq!(Scoring::for_test("1"), sprint);
+        assert_eq!(Scoring::for_test("2"), Scoring::for_test("marathon"));
+        assert_eq!(Scoring::for_test("my-map").points(1_000_000), 12.0);
+    }
+
+    #[test]
+    fn test_shipped_scoring() {
+        for (test_name, map, baseline) in [("1", "sprint", 1151), ("2", "marathon", 14207)] {
+            let path = format!("../../maps/{}.scoring", map);
+            let scoring: Scoring = fs::read_to_string(path).unwrap().parse().unwrap();
+
+            assert_eq!(scoring.baseline, Some(baseline));
+            assert_eq!(scoring.points(baseline), 12.0);
+            assert_eq!(scoring.points(baseline * 2), 1.0);
+            assert_eq!(scoring.points(baseline * 5), 0.25);
+
+            // vygenerovaný vstup vedle sebe bodování nemá, to je v adresáři s datasety
+            let context = JudgeContext {
+                test_name: test_name.to_string(),
+                seed: Some(0),
+                input_file: None,
+                input_filename: Some(format!("../../test/{}.in", test_name)),
+                reference_output_file: None,
+                dataset_dir: Some("../../maps".to_string()),
+                submission: Box::new(io::empty()),
+            };
+
+            assert_eq!(
+                sibling_path(&context, "scoring"),
+                Some(PathBuf::from(format!("../../maps/{}.scoring", map)))
+            );
+            assert_eq!(load_scoring(&context), Ok(scoring));
+        }
+    }
+
+    #[test]
+    fn test_graph_path() {
+        let context = |test_name: &str, input_filename: Option<&str>| JudgeContext {
+            test_name: test_name.to_string(),
+            seed: None,
+            input_file: None,
+            input_filename: input_filename.map(str::to_string),
+            reference_output_file: None,
+            dataset_dir: Some("../../maps".to_string()),
+            submission: Box::new(io::empty()),
+        };
+
+        let graph = |name: &str| Some(Path::new("../../maps/../graphs").join(name));
+
+        assert_eq!(graph_path(&context("sprint", None)), graph("sprint.txt"));
+        assert_eq!(graph_path(&context("1", None)), graph("sprint.txt"));
+        assert_eq!(graph_path(&context("2", None)), graph("marathon.txt"));
+
+        // graf se jmenuje podle mapy, ne podle souboru se vstupem
+        let test = context("test", Some("../../maps/test.txt"));
+        assert_eq!(graph_path(&test), graph("test.txt"));
+        let generated = context("1", Some("/tmp/opendata-check-1-0.in"));
+        assert_eq!(graph_path(&generated), graph("sprint.txt"));
+        let renamed = context("sprint", Some("../../maps/sprint-copy.txt"));
+        assert_eq!(graph_path(&renamed), graph("sprint.txt"));
+
+        assert_eq!(graph_path(&context("../sprint", None)), None);
+
+        assert!(load_graph(&context("1", None)).is_ok());
+        assert_eq!(
+            load_graph(&context("my-map", None)).err(),
+            Some(Message::MissingGraph)
+        );
+    }
+
+    #[test]
+    fn test_scoring_curves() {
+        for curve in [Curve::Hyperbolic, Curve::Linear, Curve::Logarithmic] {
+            let scoring = Scoring {
+                max_points: 10.0,
+                baseline: Some(100),
+                curve,
+                ..Scoring::default()
+            };
+
+            assert_eq!(scoring.points(100), 10.0);
+
+            let points = (100..1000).map(|length| scoring.points(length));
+            let points = points.collect::<Vec<_>>();
+
+            assert!(points.windows(2).all(|pair| pair[0] >= pair[1]));
+            assert!(points.iter().all(|&p| (0.0..=10.0).contains(&p)));
+        }
+
+        let linear = Scoring {
+            max_points: 10.0,
+            baseline: Some(100),
+            curve: Curve::Linear,
+            ..Scoring::default()
+        };
+
+        assert_eq!(linear.points(150), 5.0);
+        assert_eq!(linear.points(200), 0.0);
+        assert_eq!(linear.points(300), 0.0);
+    }
+
+    #[test]
+    fn test_scoring_from_str() {
+        let scoring = "# sprint\nmax_points = 20\n\nbaseline=500\ncurve=linear\n";
+
+        assert_eq!(
+            scoring.parse(),
+            Ok(Scoring {
+                mode: Mode::Instructions,
+                max_points: 20.0,
+                baseline: Some(500),
+                curve: Curve::Linear,
+                length: Length::Finished,
+                partial_points: false,
+            })
+        );
+
+        assert_eq!(
+            "partial_points=true"
+                .parse::<Scoring>()
+                .map(|s| s.partial_points),
+            Ok(true)
+        );
+        assert!("partial_points=1".parse::<Scoring>().is_err());
+
+        assert_eq!(
+            "length=submitted".parse::<Scoring>().map(|s| s.length),
+            Ok(Length::Submitted)
+        );
+        assert!("length=ticks".parse::<Scoring>().is_err());
+
+        assert_eq!(
+            "mode=graph\nbaseline=2000000"
+                .parse::<Scoring>()
+                .map(|s| s.mode),
+            Ok(Mode::Graph)
+        );
+        // délka cesty nejsou tiky, výchozí baseline by nedávaly smysl
+        assert_eq!("mode=graph".parse::<Scoring>(), Err("baseline".to_string()));
+        assert!("mode=path".parse::<Scoring>().is_err());
+
+        assert_eq!("".parse(), Ok(Scoring::default()));
+
+        assert_eq!("curve=cubic".parse::<Scoring>(), Err("curve".to_string()));
+        assert_eq!("baseline=0".parse::<Scoring>(), Err("baseline".to_string()));
+        assert_eq!(
+            "max_points=-1".parse::<Scoring>(),
+            Err("max_points".to_string())
+        );
+        assert_eq!("points=1".parse::<Scoring>(), Err("points".to_string()));
+        assert_eq!("baseline".parse::<Scoring>(), Err("baseline".to_string()));
+    }
+
+    #[test]
+    fn test_truncate_message() {
+        assert_eq!(truncate_message("krátká".to_string()), "krátká");
+
+        for message in ["a".repeat(300), "č".repeat(300)] {
+            let truncated = truncate_message(message.clone());
+
+            assert!(truncated.len() <= MESSAGE_LIMIT);
+            assert!(truncated.ends_with('…'));
+            assert!(message.starts_with(truncated.trim_end_matches('…')));
+        }
+    }
+
+    #[test]
+    fn test_partial_points_and_report() {
+        let map = fs::read_to_string("../../test/solves/test.txt").unwrap();
+        let simulation = Simulation::from_str(&map).unwrap();
+
+        // only the first half of the solve, so some goals are reached but not all
+        let solve = fs::read_to_string("../../test/solves/test.in").unwrap();
+        let instructions = solve.lines().skip(1).take(150).collect::<Vec<_>>();
+        let submission = format!("{}\n{}\n", instructions.len(), instructions.join("\n"));
+
+        let report = Report::run(
+            &mut simulation.clone(),
+            &Instruction::from_reader(submission.as_bytes()).unwrap(),
+        );
+        let (reached, total) = (report.reached_goals(), report.goal_ticks.len());
+        assert!(0 < reached && reached < total);
+
+        let options = JudgeOptions {
+            language: Language::English,
+            report: true,
+            ..JudgeOptions::default()
+        };
+
+        let scoring = Scoring::default();
+        let verdict =
+            judge_submission(simulation.clone(), &scoring, submission.as_bytes(), options);
+
+        assert_eq!(verdict.verdict_type(), VerdictType::Wrong);
+        assert_eq!(verdict.get_points(), None);
+
+        let message = verdict.get_message().unwrap();
+        assert!(message.ends_with(&Message::Report(report).text(Language::English)));
+
+        let scoring = Scoring {
+            partial_points: true,
+            ..Scoring::default()
+        };
+        let verdict = judge_submission(simulation, &scoring, submission.as_bytes(), options);
+
+        assert_eq!(verdict.verdict_type(), VerdictType::Correct);
+        assert_eq!(
+            verdict.get_points(),
+            Some(12.0 * reached as f64 / total as f64)
+        );
+    }
+
+    #[test]
+    fn test_trailing_instructions() {
+        let map = fs::read_to_string("../../test/solves/sprint.txt").unwrap();
+        let simulation = Simulation::from_str(&map).unwrap();
+
+        let solve = fs::read_to_string("../../test/solves/sprint.in").unwrap();
+        let mut instructions = solve.lines().skip(1).collect::<Vec<_>>();
+        instructions.extend(["0 0"; 500]);
+        let submission = format!("{}\n{}\n", instructions.len(), instructions.join("\n"));
+
+        let points = |length| {
+            let scoring = Scoring {
+                length,
+                ..Scoring::for_test("sprint")
+            };
+            let verdict = judge_submission(
+                simulation.clone(),
+                &scoring,
+                submission.as_bytes(),
+                JudgeOptions::default(),
+            );
+
+            verdict.get_points().unwrap()
+        };
+
+        let ticks = simulation
+            .clone()
+            .simulate_until_finished(&Instruction::from_reader(submission.as_bytes()).unwrap())
+            .unwrap();
+
+        // the junk at the end only costs points when counting the submitted instructions
+        assert_eq!(
+            points(Length::Finished),
+            Scoring::for_test("sprint").points(ticks)
+        );
+        assert!(points(Length::Submitted) < points(Length::Finished));
+    }
+}
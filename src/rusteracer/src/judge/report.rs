@@ -0,0 +1,193 @@
+//! Podrobný průběh letu, aby řešitelé věděli, co se pokazilo.
+use crate::simulation::*;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Report {
+    pub ticks: usize,                             // počet odsimulovaných instrukcí
+    pub goal_ticks: Vec<Option<usize>>,           // tik (od jedničky), kdy byl cíl dosažen
+    pub finished_tick: Option<usize>,             // tik, kdy byly dosaženy všechny cíle
+    pub closest_approaches: Option<Vec<PosType>>, // nejmenší vzdálenost od cílů (viz `detailed`)
+    pub collisions: usize,                        // počet tiků s kolizí
+    pub racer: Racer,                             // stav závodníka po poslední instrukci
+}
+
+impl Report {
+    /// Začne záznam z aktuálního stavu simulace.
+    pub fn new(simulation: &Simulation) -> Self {
+        let goals = &simulation.map.goals;
+
+        Report {
+            ticks: 0,
+            goal_ticks: vec![None; goals.len()],
+            finished_tick: simulation.finished().then_some(0),
+            closest_approaches: None,
+            collisions: 0,
+            racer: simulation.racer,
+        }
+    }
+
+    /// Jako [`Report::new`], ale sleduje i nejmenší vzdálenost okraje závodníka od okraje
+    /// každého cíle. To stojí O(cílů) za tik, takže jen když se průběh opravdu vypisuje.
+    pub fn detailed(simulation: &Simulation) -> Self {
+        let goals = &simulation.map.goals;
+
+        let mut report = Report {
+            closest_approaches: Some(vec![PosType::MAX; goals.len()]),
+            ..Report::new(simulation)
+        };
+
+        report.update_closest_approaches(&simulation.racer, goals);
+        report
+    }
+
+    /// Odsimuluje jednu instrukci a zaznamená ji.
+    pub fn tick(&mut self, simulation: &mut Simulation, instruction: Instruction) {
+        let result = simulation.tick(instruction);
+        self.ticks += 1;
+
+        if result & TickFlag::COLLIDED != 0 {
+            self.collisions += 1;
+        }
+
+        if result & TickFlag::GOAL_REACHED != 0 {
+            for (goal_tick, &reached) in self.goal_ticks.iter_mut().zip(&simulation.reached_goals) {
+                if reached && goal_tick.is_none() {
+                    *goal_tick = Some(self.ticks);
+                }
+            }
+
+            // dokončit jde jen dosažením cíle, jinak by to stálo O(cílů) za každý tik
+            if self.finished_tick.is_none() && simulation.finished() {
+                self.finished_tick = Some(self.ticks);
+            }
+        }
+
+        self.update_closest_approaches(&simulation.racer, &simulation.map.goals);
+
+        self.racer = simulation.racer;
+    }
+
+    /// Odsimuluje instrukce od začátku mapy a zaznamená průběh.
+    #[allow(dead_code)] // soudce simuluje instrukce postupně, jak je čte
+    pub fn run(simulation: &mut Simulation, instructions: &[Instruction]) -> Self {
+        simulation.restart();
+
+        let mut report = Report::detailed(simulation);
+
+        for &instruction in instructions {
+            report.tick(simulation, instruction);
+        }
+
+        report
+    }
+
+    fn update_closest_approaches(&mut self, racer: &Racer, goals: &[Goal]) {
+        let Some(closest_approaches) = &mut self.closest_approaches else {
+            return;
+        };
+
+        for (closest, goal) in closest_approaches.iter_mut().zip(goals) {
+            let distance = euclidean_distance(racer.x, racer.y, goal.x, goal.y)
+                .saturating_sub(racer.radius + goal.radius)
+                .max(0);
+
+            *closest = (*closest).min(distance);
+        }
+    }
+
+    pub fn reached_goals(&self) -> usize {
+        self.goal_ticks.iter().flatten().count()
+    }
+
+    /// Čísla nedosažených cílů (od jedničky).
+    pub fn unreached_goals(&self) -> Vec<usize> {
+        (self.goal_ticks.iter().enumerate())
+            .filter_map(|(i, tick)| if tick.is_none() { Some(i + 1) } else { None })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report() {
+        // racer at the origin, one goal straight up, one far to the side
+        let mut simulation = Simulation::new(
+            Racer {
+                x: 0,
+                y: 0,
+                vx: 0,
+                vy: 0,
+                radius: 1,
+            },
+            vec![],
+            vec![
+                Goal {
+                    x: 0,
+                    y: 1000,
+                    radius: 100,
+                },
+                Goal {
+                    x: 100_000,
+                    y: 0,
+                    radius: 100,
+                },
+            ],
+            BoundingBox {
+                min_x: -200_000,
+                min_y: -200_000,
+                max_x: 200_000,
+                max_y: 200_000,
+            },
+        );
+
+        let instructions = vec![Instruction::new(0, 10); 40];
+        let report = Report::run(&mut simulation, &instructions);
+
+        let tick = report.goal_ticks[0].unwrap();
+
+        assert!(0 < tick && tick <= instructions.len());
+        assert_eq!(report.goal_ticks[1], None);
+        assert_eq!(report.finished_tick, None);
+        assert_eq!(report.reached_goals(), 1);
+        assert_eq!(report.unreached_goals(), vec![2]);
+
+        // the closest the racer got to the second goal was the start
+        assert_eq!(
+            report.closest_approaches.as_ref().unwrap()[1],
+            100_000 - 1 - 100
+        );
+        assert_eq!(report.collisions, 0);
+        assert_eq!(report.racer, simulation.racer);
+
+        // without the closest approaches, the rest is the same
+        let mut plain = Report::new(&Simulation::from(simulation.map.clone()));
+        let mut replay = Simulation::from(simulation.map.clone());
+        for &instruction in &instructions {
+            plain.tick(&mut replay, instruction);
+        }
+        assert_eq!(plain.closest_approaches, None);
+        assert_eq!(
+            plain,
+            Report {
+                closest_approaches: None,
+                ..report.clone()
+            }
+        );
+
+        // reaching the first goal on the exact tick it's reported
+        let mut replay = Simulation::from(simulation.map.clone());
+        replay.simulate(&instructions[..tick - 1]);
+        assert!(!replay.reached_goals[0]);
+        replay.tick(instructions[tick - 1]);
+        assert!(replay.reached_goals[0]);
+
+        // without the far goal, the map is finished by reaching the first one
+        let mut map = simulation.map.description();
+        map.goals.truncate(1);
+        let report = Report::run(&mut Simulation::from(map), &instructions);
+        assert_eq!(report.finished_tick, Some(tick));
+    }
+}
@@ -1,23 +1,32 @@
 mod example;
+mod generator;
+mod grid;
 mod judge;
 mod opendata;
 mod simulation;
 mod solve;
 
 use crate::opendata::OpenData;
-use crate::simulation::*;
 
 pub fn main() {
     OpenData::new()
+        .add_generator(generator::generate)
+        .test_count(generator::TESTS.len())
         .add_judge(judge::judge)
-        // .add_solver("--solve", solve::solve_by_max_reach)
-        // .add_solver("--solve-empty", || { println!("0") })
-        // .add_solver("--solve-random", solve::solve_random)
+        .add_solver_with_description(
+            "--solve",
+            "Evolves instructions reaching the most goals until the time limit.",
+            solve::solve_by_max_reach,
+        )
+        .add_solver_with_description("--solve-empty", "Submits no instructions.", |_| {
+            println!("0")
+        })
         .handle();
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::simulation::*;
     use crate::*;
     use std::fs;
     use std::path::PathBuf;
@@ -41,9 +50,9 @@ mod tests {
 
             for (char, goal) in parts[4].chars().zip(&simulation.reached_goals) {
                 if *goal {
-                    assert_eq!(char, '1', "{}", format!("States differ after line {}", i));
+                    assert_eq!(char, '1', "States differ after line {}", i);
                 } else {
-                    assert_eq!(char, '0', "{}", format!("States differ after line {}", i));
+                    assert_eq!(char, '0', "States differ after line {}", i);
                 }
             }
         }
@@ -53,18 +62,16 @@ mod tests {
         let mut samples = vec![];
 
         if let Ok(entries) = fs::read_dir(dir) {
-            for entry in entries {
-                if let Ok(entry) = entry {
-                    let path = entry.path();
-
-                    if let Some(file_name) = path.file_name().and_then(|s| s.to_str()) {
-                        if file_name.ends_with("txt") {
-                            let map_file = path.clone();
-                            let input_file = path.with_extension("in");
-                            let output_file = path.with_extension("out");
-
-                            samples.push((map_file, input_file, output_file))
-                        }
+            for entry in entries.flatten() {
+                let path = entry.path();
+
+                if let Some(file_name) = path.file_name().and_then(|s| s.to_str()) {
+                    if file_name.ends_with("txt") {
+                        let map_file = path.clone();
+                        let input_file = path.with_extension("in");
+                        let output_file = path.with_extension("out");
+
+                        samples.push((map_file, input_file, output_file))
                     }
                 }
             }
@@ -77,10 +84,16 @@ mod tests {
     #[test]
     fn test_states() {
         for (map, instructions, states) in find_equal_states_cases("../../test/states/") {
+            // some of the traces are too large to be included in the repository
+            if !states.exists() {
+                println!("Skipping '{:?}', no states to compare with", map);
+                continue;
+            }
+
             println!("Verifying equal states for '{:?}'", map);
 
-            let mut simulation = Simulation::load(&map);
-            let instructions = Instruction::load(&instructions);
+            let mut simulation = Simulation::load(&map).unwrap();
+            let instructions = Instruction::load(&instructions).unwrap();
 
             verify_equal_states(&mut simulation, &instructions, states);
         }
@@ -92,8 +105,8 @@ mod tests {
         for (map, instructions, _) in find_equal_states_cases("../../test/solves/") {
             println!("Verifying solves for '{:?}'", map);
 
-            let mut simulation = Simulation::load(&map);
-            let instructions = Instruction::load(&instructions);
+            let mut simulation = Simulation::load(&map).unwrap();
+            let instructions = Instruction::load(&instructions).unwrap();
 
             simulation.simulate(&instructions);
 
@@ -102,7 +115,7 @@ mod tests {
                     .ok()
                     .unwrap();
 
-            let (distance, shortest_path) =
+            let (_, shortest_path) =
                 solve::shortest_path(&vertices, &edges, &vertex_objects).unwrap();
 
             println!("Shortest path: {:?}", shortest_path);
@@ -123,7 +136,34 @@ mod tests {
     /// Test that the sample implementation runs. Just shouldn't crash, that's all.
     #[test]
     fn test_example_works() {
-        example::main()
+        // don't overwrite the committed best solution
+        let best_path = std::env::temp_dir().join(format!("best-{}.txt", std::process::id()));
+
+        example::main(&best_path);
+
+        // the best solution found should be loadable again
+        Instruction::load(&best_path).unwrap();
+
+        fs::remove_file(&best_path).ok();
+    }
+
+    /// Test that the anytime solver improves on doing nothing and stops when told to.
+    #[test]
+    fn test_evolve_by_max_reach() {
+        let map = Map::load(&PathBuf::from("../../maps/test.txt")).unwrap();
+
+        let mut initial = solve::Individual::new(map.initial_state(), vec![]);
+        initial.evaluate_reach(&map);
+
+        let best = solve::evolve_by_max_reach(&map, |generation| generation >= 100);
+        assert!(best.fitness > initial.fitness);
+
+        // its instructions lead to the state it was evaluated in
+        let result = map.simulate_from(map.initial_state(), &best.instructions);
+        assert_eq!(result.state, best.state);
+
+        let stopped = solve::evolve_by_max_reach(&map, |_| true);
+        assert!(stopped.instructions.is_empty());
     }
 
     /// Test that we can load the asteroid graphs.
@@ -0,0 +1,446 @@
+//! Command line arguments of [`super::OpenData`]: which program to run and with what.
+//!
+//! Parsing never exits the program, so it can be tested; [`super::OpenData::handle`] turns
+//! the errors into messages and exit codes.
+use super::{parse_seed, OpenData};
+use std::fmt;
+
+/// Exit code for invalid arguments, a common convention for command line tools.
+pub const USAGE_EXIT_CODE: i32 = 2;
+
+/// One of the programs in the binary.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Program {
+    Generator,
+    Judge,
+    Check,
+    Solver(String),
+}
+
+impl Program {
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "--gen" => Some(Program::Generator),
+            "--judge" => Some(Program::Judge),
+            "--check" => Some(Program::Check),
+            "--help" | "-h" | "--list" => None,
+            solver => Some(Program::Solver(solver.to_string())),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        match self {
+            Program::Generator => "--gen",
+            Program::Judge => "--judge",
+            Program::Check => "--check",
+            Program::Solver(name) => name,
+        }
+    }
+
+    /// The arguments of the program, for usage messages.
+    fn arguments(&self) -> &'static str {
+        match self {
+            Program::Generator => "<test_id> <seed>",
+            Program::Judge => "<test_id> <seed>",
+            Program::Check => "<test_id or input> <solver or command or output> [seed]",
+            Program::Solver(_) => "[test_id] [seed]",
+        }
+    }
+
+    fn is_added(&self, opendata: &OpenData) -> bool {
+        match self {
+            Program::Generator => opendata.generate_handler.is_some(),
+            Program::Judge | Program::Check => opendata.judge_handler.is_some(),
+            Program::Solver(name) => opendata.solve_handlers.contains_key(name),
+        }
+    }
+
+    fn description(&self, opendata: &OpenData) -> String {
+        match self {
+            Program::Generator => {
+                "Generates the input of the test (a number) from the seed (hexadecimal) to stdout."
+                    .to_string()
+            }
+            Program::Judge => "Judges the output on stdin. The seed is hexadecimal, \
+                               or `-` for example inputs."
+                .to_string(),
+            Program::Check => "Runs the solver on the test and judges its output. The test is \
+                               a test number (the input is generated, the seed is 0 by default) \
+                               or an input file. The solver is the name of a solver of this \
+                               program, an executable, a file with the output, or a shell command."
+                .to_string(),
+            Program::Solver(name) => format!(
+                "{} The test name and seed are optional, the time limit in seconds is set by {}.",
+                (opendata.solve_handlers.get(name))
+                    .and_then(|solver| solver.description.as_deref())
+                    .unwrap_or("Solves the input on stdin."),
+                super::SOLVER_TIME_LIMIT_VAR
+            ),
+        }
+    }
+}
+
+/// What the arguments ask for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Action {
+    Generate(usize, u64),                       // test number, seed
+    Judge(String, Option<u64>),                 // test name, seed (`None` for example inputs)
+    Check(String, String, u64),                 // test, solver, seed
+    Solve(String, Option<String>, Option<u64>), // solver name, test name, seed
+    Help(Option<Program>),                      // help for one program, or all of them
+    List,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ArgsError {
+    MissingProgram,            // no arguments at all
+    UnknownProgram(String),    // no such program was added
+    NotAdded(Program),         // a built-in program whose handler wasn't added
+    MissingArguments(Program), // the program needs more arguments
+    InvalidTestNumber(String),
+    UnknownTest(usize, usize), // test number, number of tests
+    InvalidSeed(String),
+}
+
+impl fmt::Display for ArgsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ArgsError::MissingProgram => write!(f, "no program given"),
+            ArgsError::UnknownProgram(name) => write!(f, "unknown program '{}'", name),
+            ArgsError::NotAdded(program) => {
+                write!(f, "'{}' is not available in this binary", program.name())
+            }
+            ArgsError::MissingArguments(program) => write!(
+                f,
+                "missing arguments, usage: {} {}",
+                program.name(),
+                program.arguments()
+            ),
+            ArgsError::InvalidTestNumber(test) => {
+                write!(f, "the test number '{}' is not an unsigned integer", test)
+            }
+            ArgsError::UnknownTest(test, count) => {
+                write!(f, "there is no test {}, the tests are 1 to {}", test, count)
+            }
+            ArgsError::InvalidSeed(seed) => {
+                write!(f, "the seed '{}' is not a hexadecimal number", seed)
+            }
+        }
+    }
+}
+
+/// The program with this name, if it was added.
+fn added(opendata: &OpenData, name: &str) -> Result<Program, ArgsError> {
+    match Program::parse(name) {
+        Some(program) if program.is_added(opendata) => Ok(program),
+        // anything that isn't a program of ours is taken as a solver name
+        Some(Program::Solver(_)) | None => Err(ArgsError::UnknownProgram(name.to_string())),
+        Some(program) => Err(ArgsError::NotAdded(program)),
+    }
+}
+
+/// Checks that the test exists, if the number of tests is known.
+fn existing_test(opendata: &OpenData, test: usize) -> Result<usize, ArgsError> {
+    match opendata.test_count {
+        Some(count) if !(1..=count).contains(&test) => Err(ArgsError::UnknownTest(test, count)),
+        _ => Ok(test),
+    }
+}
+
+fn seed(seed: &str) -> Result<u64, ArgsError> {
+    parse_seed(seed).map_err(|_| ArgsError::InvalidSeed(seed.to_string()))
+}
+
+/// Parses the arguments, without the name of the binary.
+pub fn parse(opendata: &OpenData, args: &[String]) -> Result<Action, ArgsError> {
+    let args = args.iter().map(String::as_str).collect::<Vec<_>>();
+
+    let (name, args) = args.split_first().ok_or(ArgsError::MissingProgram)?;
+
+    match (*name, args) {
+        ("--help" | "-h", []) => return Ok(Action::Help(None)),
+        ("--help" | "-h", [name, ..]) => return Ok(Action::Help(Some(added(opendata, name)?))),
+        ("--list", _) => return Ok(Action::List),
+        _ => {}
+    }
+
+    let program = added(opendata, name)?;
+
+    if args
+        .first()
+        .is_some_and(|arg| matches!(*arg, "--help" | "-h"))
+    {
+        return Ok(Action::Help(Some(program)));
+    }
+
+    // other tests of `--check` are input files
+    if let (Program::Check, [test, ..]) = (&program, args) {
+        if let Ok(test) = test.parse() {
+            existing_test(opendata, test)?;
+        }
+    }
+
+    match (program, args) {
+        (Program::Generator, [test, seed_, ..]) => Ok(Action::Generate(
+            existing_test(
+                opendata,
+                (test.parse()).map_err(|_| ArgsError::InvalidTestNumber(test.to_string()))?,
+            )?,
+            seed(seed_)?,
+        )),
+        (Program::Judge, [test_name, "-", ..]) => Ok(Action::Judge(test_name.to_string(), None)),
+        (Program::Judge, [test_name, seed_, ..]) => {
+            Ok(Action::Judge(test_name.to_string(), Some(seed(seed_)?)))
+        }
+        (Program::Check, [test, solver]) => {
+            Ok(Action::Check(test.to_string(), solver.to_string(), 0))
+        }
+        (Program::Check, [test, solver, seed_, ..]) => Ok(Action::Check(
+            test.to_string(),
+            solver.to_string(),
+            seed(seed_)?,
+        )),
+        (Program::Solver(name), []) => Ok(Action::Solve(name, None, None)),
+        (Program::Solver(name), [test_name]) => {
+            Ok(Action::Solve(name, Some(test_name.to_string()), None))
+        }
+        (Program::Solver(name), [test_name, "-", ..]) => {
+            Ok(Action::Solve(name, Some(test_name.to_string()), None))
+        }
+        (Program::Solver(name), [test_name, seed_, ..]) => Ok(Action::Solve(
+            name,
+            Some(test_name.to_string()),
+            Some(seed(seed_)?),
+        )),
+        (program, _) => Err(ArgsError::MissingArguments(program)),
+    }
+}
+
+/// All programs added to the binary, in the order they are listed.
+fn programs(opendata: &OpenData) -> Vec<Program> {
+    let mut programs = vec![];
+
+    if opendata.generate_handler.is_some() {
+        programs.push(Program::Generator);
+    }
+
+    if opendata.judge_handler.is_some() {
+        programs.push(Program::Judge);
+        programs.push(Program::Check);
+    }
+
+    for name in opendata.solve_handlers.keys() {
+        programs.push(Program::Solver(name.clone()));
+    }
+
+    programs
+}
+
+/// Usage of the whole binary, or of one program.
+pub fn usage(opendata: &OpenData, binary: &str, program: Option<&Program>) -> String {
+    let line = |program: &Program| {
+        format!("{} {} {}", binary, program.name(), program.arguments())
+            .trim_end()
+            .to_string()
+    };
+
+    if let Some(program) = program {
+        return format!(
+            "Usage: {}\n\n{}\n",
+            line(program),
+            program.description(opendata)
+        );
+    }
+
+    let programs = programs(opendata);
+
+    if programs.is_empty() {
+        return "This program does nothing because no handlers were added; \
+                make sure to read the documentation.\n"
+            .to_string();
+    }
+
+    let mut usage = "This is a single binary for multiple programs, see usage:\n".to_string();
+
+    for program in &programs {
+        usage += &format!("\t{}\n", line(program));
+    }
+
+    usage += &format!("\t{} --list\n", binary);
+    usage += &format!("\t{} --help [program]\n", binary);
+    usage
+}
+
+/// The added programs with their descriptions, one per line and separated by a tab.
+pub fn list(opendata: &OpenData) -> String {
+    (programs(opendata).iter())
+        .map(|program| format!("{}\t{}\n", program.name(), program.description(opendata)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::opendata::judge::Verdict;
+
+    fn opendata() -> OpenData {
+        OpenData::new()
+            .add_generator(|_, _| {})
+            .test_count(2)
+            .add_judge(|_| Verdict::correct())
+            .add_solver("--solve", |_| {})
+            .add_solver_with_description("--solve-empty", "Submits no instructions.", |_| {})
+    }
+
+    fn parse_str(opendata: &OpenData, args: &str) -> Result<Action, ArgsError> {
+        let args = args
+            .split_whitespace()
+            .map(String::from)
+            .collect::<Vec<_>>();
+        parse(opendata, &args)
+    }
+
+    #[test]
+    fn test_parse() {
+        let opendata = opendata();
+        let parse = |args| parse_str(&opendata, args);
+
+        assert_eq!(parse("--gen 2 ff"), Ok(Action::Generate(2, 0xff)));
+        assert_eq!(
+            parse("--judge sprint -"),
+            Ok(Action::Judge("sprint".into(), None))
+        );
+        assert_eq!(
+            parse("--judge 1 a"),
+            Ok(Action::Judge("1".into(), Some(0xa)))
+        );
+        assert_eq!(
+            parse("--check 1 --solve"),
+            Ok(Action::Check("1".into(), "--solve".into(), 0))
+        );
+        assert_eq!(
+            parse("--check 1 --solve 3"),
+            Ok(Action::Check("1".into(), "--solve".into(), 3))
+        );
+        assert_eq!(
+            parse("--solve"),
+            Ok(Action::Solve("--solve".into(), None, None))
+        );
+        assert_eq!(
+            parse("--solve 1 -"),
+            Ok(Action::Solve("--solve".into(), Some("1".into()), None))
+        );
+        assert_eq!(
+            parse("--solve sprint 2a"),
+            Ok(Action::Solve(
+                "--solve".into(),
+                Some("sprint".into()),
+                Some(0x2a)
+            ))
+        );
+        assert_eq!(parse("--list"), Ok(Action::List));
+    }
+
+    #[test]
+    fn test_parse_help() {
+        let opendata = opendata();
+        let parse = |args| parse_str(&opendata, args);
+
+        assert_eq!(parse("--help"), Ok(Action::Help(None)));
+        assert_eq!(parse("-h"), Ok(Action::Help(None)));
+        assert_eq!(
+            parse("--gen --help"),
+            Ok(Action::Help(Some(Program::Generator)))
+        );
+        assert_eq!(
+            parse("--help --judge"),
+            Ok(Action::Help(Some(Program::Judge)))
+        );
+        assert_eq!(
+            parse("--solve-empty -h"),
+            Ok(Action::Help(Some(Program::Solver("--solve-empty".into()))))
+        );
+        assert_eq!(
+            parse("--help --nope"),
+            Err(ArgsError::UnknownProgram("--nope".into()))
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let opendata = opendata();
+        let parse = |args| parse_str(&opendata, args);
+
+        assert_eq!(parse(""), Err(ArgsError::MissingProgram));
+        assert_eq!(
+            parse("--nope"),
+            Err(ArgsError::UnknownProgram("--nope".into()))
+        );
+        assert_eq!(
+            parse("--gen 2"),
+            Err(ArgsError::MissingArguments(Program::Generator))
+        );
+        assert_eq!(
+            parse("--gen x ff"),
+            Err(ArgsError::InvalidTestNumber("x".into()))
+        );
+        assert_eq!(parse("--gen 3 ff"), Err(ArgsError::UnknownTest(3, 2)));
+        assert_eq!(parse("--gen 0 ff"), Err(ArgsError::UnknownTest(0, 2)));
+        assert_eq!(
+            parse("--check 3 --solve"),
+            Err(ArgsError::UnknownTest(3, 2))
+        );
+        assert_eq!(
+            parse("--gen 2 xyz"),
+            Err(ArgsError::InvalidSeed("xyz".into()))
+        );
+        assert_eq!(
+            parse("--judge 2 -1"),
+            Err(ArgsError::InvalidSeed("-1".into()))
+        );
+        assert_eq!(
+            parse("--check 1"),
+            Err(ArgsError::MissingArguments(Program::Check))
+        );
+
+        // nothing is added, so nothing can run
+        let empty = OpenData::new();
+        assert_eq!(
+            parse_str(&empty, "--gen 2 ff"),
+            Err(ArgsError::NotAdded(Program::Generator))
+        );
+        assert_eq!(
+            parse_str(&empty, "--check 1 --solve"),
+            Err(ArgsError::NotAdded(Program::Check))
+        );
+        assert_eq!(
+            parse_str(&empty, "--solve"),
+            Err(ArgsError::UnknownProgram("--solve".into()))
+        );
+    }
+
+    #[test]
+    fn test_usage_and_list() {
+        let opendata = opendata();
+
+        let usage = usage(&opendata, "ksp", None);
+        assert!(usage.contains("ksp --gen <test_id> <seed>\n"));
+        assert!(usage.contains("ksp --solve-empty [test_id] [seed]\n"));
+
+        let help = super::usage(
+            &opendata,
+            "ksp",
+            Some(&Program::Solver("--solve-empty".into())),
+        );
+        assert!(help.contains("Submits no instructions."));
+
+        assert_eq!(
+            list(&opendata)
+                .lines()
+                .map(|line| line.split('\t').next().unwrap())
+                .collect::<Vec<_>>(),
+            ["--gen", "--judge", "--check", "--solve", "--solve-empty"]
+        );
+        assert_eq!(list(&OpenData::new()), "");
+    }
+}
@@ -0,0 +1,273 @@
+//! Local end-to-end checks: generate an input, run a solver on it and judge its output.
+//!
+//! This is what `--check <test> <solver> [seed]` does, so submissions can be regression-tested
+//! without emulating the opendata environment (`TEST_INPUT`, exit codes 42/43, ...) by hand.
+//!
+//! The test is either a test number, in which case the input is generated by running this
+//! program with `--gen`, or a path to an existing input (like an example input).
+//!
+//! The solver is, in this order of preference:
+//! * the name of a solver added by [`super::OpenData::add_solver`],
+//! * an existing file; executable files are run, other files are taken as the solver output,
+//! * a shell command.
+use super::JudgeContext;
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, Cursor};
+use std::path::{Path, PathBuf};
+use std::process::{self, Command, Stdio};
+
+/// Where the input for the check comes from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TestInput {
+    Generated(usize, u64), // test number and seed for the generator
+    File(PathBuf),         // an existing input, its file stem is the test name
+}
+
+impl TestInput {
+    pub fn parse(test: &str, seed: u64) -> Self {
+        match test.parse() {
+            Ok(test_number) => TestInput::Generated(test_number, seed),
+            Err(_) => TestInput::File(PathBuf::from(test)),
+        }
+    }
+
+    pub fn test_name(&self) -> String {
+        match self {
+            TestInput::Generated(test_number, _) => test_number.to_string(),
+            TestInput::File(path) => (path.file_stem())
+                .map(|stem| stem.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+        }
+    }
+
+    pub fn seed(&self) -> Option<u64> {
+        match self {
+            TestInput::Generated(_, seed) => Some(*seed),
+            TestInput::File(_) => None,
+        }
+    }
+}
+
+/// How to get the output that will be judged.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Solver {
+    Registered(String), // a solver of this program, run as `<program> <name> <test> <seed>`
+    Executable(PathBuf),
+    Output(PathBuf), // a file with an already computed output
+    Command(String), // run with `sh -c`
+}
+
+impl Solver {
+    pub fn parse<'a>(solver: &str, mut registered: impl Iterator<Item = &'a String>) -> Self {
+        let path = Path::new(solver);
+
+        if registered.any(|name| name == solver) {
+            Solver::Registered(solver.to_string())
+        } else if path.is_file() && is_executable(path) {
+            Solver::Executable(path.to_path_buf())
+        } else if path.is_file() {
+            Solver::Output(path.to_path_buf())
+        } else {
+            Solver::Command(solver.to_string())
+        }
+    }
+
+    /// Runs the solver on the input of the test and returns its output.
+    pub fn run(&self, test: &TestInput, input: &Path) -> io::Result<Box<dyn BufRead>> {
+        let mut command = match self {
+            Solver::Registered(name) => {
+                let seed = test
+                    .seed()
+                    .map_or("-".to_string(), |seed| format!("{:x}", seed));
+
+                let mut command = Command::new(env::current_exe()?);
+                command.arg(name).arg(test.test_name()).arg(seed);
+                command
+            }
+            Solver::Executable(path) => Command::new(path),
+            Solver::Output(path) => return Ok(Box::new(BufReader::new(File::open(path)?))),
+            Solver::Command(command) => {
+                let mut shell = Command::new("sh");
+                shell.arg("-c").arg(command);
+                shell
+            }
+        };
+
+        let output = run(command.stdin(File::open(input)?))?;
+
+        Ok(Box::new(Cursor::new(output)))
+    }
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+
+    fs::metadata(path).is_ok_and(|metadata| metadata.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.extension().is_some_and(|extension| extension == "exe")
+}
+
+/// Runs the command and returns its stdout, failing if the command fails.
+fn run(command: &mut Command) -> io::Result<Vec<u8>> {
+    let output = command.stderr(Stdio::inherit()).output()?;
+
+    if !output.status.success() {
+        return Err(io::Error::other(format!(
+            "{:?} failed: {}",
+            command.get_program(),
+            output.status
+        )));
+    }
+
+    Ok(output.stdout)
+}
+
+/// Generates the input of a test from its number and seed.
+pub type Generate = dyn Fn(usize, u64) -> io::Result<Vec<u8>>;
+
+/// Generates the input by running this program as a generator.
+pub fn run_generator(test_number: usize, seed: u64) -> io::Result<Vec<u8>> {
+    run(Command::new(env::current_exe()?)
+        .arg("--gen")
+        .arg(test_number.to_string())
+        .arg(format!("{:x}", seed)))
+}
+
+/// An input file for the check, removed when dropped if it was generated.
+pub struct Input {
+    path: PathBuf,
+    generated: bool,
+}
+
+impl Input {
+    /// Generates the input with `generate` (normally [`run_generator`]), or uses the existing
+    /// file.
+    pub fn prepare(test: &TestInput, generate: &Generate) -> io::Result<Self> {
+        match test {
+            TestInput::File(path) => Ok(Input {
+                path: path.clone(),
+                generated: false,
+            }),
+            &TestInput::Generated(test_number, seed) => {
+                let output = generate(test_number, seed)?;
+
+                let path = env::temp_dir().join(format!(
+                    "opendata-check-{}-{}-{:x}.in",
+                    process::id(),
+                    test_number,
+                    seed
+                ));
+
+                fs::write(&path, output)?;
+
+                Ok(Input {
+                    path,
+                    generated: true,
+                })
+            }
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The context a judge would get for this input in opendata, with the given submission.
+    pub fn judge_context(
+        &self,
+        test: &TestInput,
+        submission: Box<dyn BufRead>,
+    ) -> io::Result<JudgeContext> {
+        Ok(JudgeContext {
+            test_name: test.test_name(),
+            seed: test.seed(),
+            input_file: Some(File::open(&self.path)?),
+            input_filename: Some(self.path.to_string_lossy().into_owned()),
+            reference_output_file: None,
+            dataset_dir: super::dataset_dir(),
+            submission,
+        })
+    }
+}
+
+impl Drop for Input {
+    fn drop(&mut self) {
+        if self.generated {
+            fs::remove_file(&self.path).ok();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    fn read_all(mut reader: Box<dyn BufRead>) -> String {
+        let mut output = String::new();
+        reader.read_to_string(&mut output).unwrap();
+        output
+    }
+
+    #[test]
+    fn test_parse_test_input() {
+        let test = TestInput::parse("2", 0xff);
+        assert_eq!(test, TestInput::Generated(2, 0xff));
+        assert_eq!(test.test_name(), "2");
+        assert_eq!(test.seed(), Some(0xff));
+
+        let test = TestInput::parse("../../maps/sprint.txt", 0xff);
+        assert_eq!(
+            test,
+            TestInput::File(PathBuf::from("../../maps/sprint.txt"))
+        );
+        assert_eq!(test.test_name(), "sprint");
+        assert_eq!(test.seed(), None);
+    }
+
+    #[test]
+    fn test_parse_solver() {
+        let registered = ["--solve".to_string()];
+        let parse = |solver| Solver::parse(solver, registered.iter());
+
+        assert_eq!(parse("--solve"), Solver::Registered("--solve".to_string()));
+        assert_eq!(
+            parse("../../test/solves/sprint.in"),
+            Solver::Output(PathBuf::from("../../test/solves/sprint.in"))
+        );
+        assert_eq!(parse("echo 0"), Solver::Command("echo 0".to_string()));
+    }
+
+    #[test]
+    fn test_run_solver() {
+        let input = Path::new("../../maps/test.txt");
+        let test = TestInput::File(input.to_path_buf());
+
+        // the command gets the input on stdin
+        let output = Solver::Command("head -n 1".to_string())
+            .run(&test, input)
+            .unwrap();
+        let first_line = fs::read_to_string(input).unwrap();
+        assert_eq!(
+            read_all(output),
+            format!("{}\n", first_line.lines().next().unwrap())
+        );
+
+        let output = Solver::Output(PathBuf::from("../../test/solves/test.in"))
+            .run(&test, input)
+            .unwrap();
+        assert_eq!(
+            read_all(output),
+            fs::read_to_string("../../test/solves/test.in").unwrap()
+        );
+
+        assert!(Solver::Command("exit 3".to_string())
+            .run(&test, input)
+            .is_err());
+    }
+}
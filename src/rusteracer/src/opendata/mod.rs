@@ -1,4 +1,5 @@
 #![allow(dead_code)]
+#![allow(clippy::needless_doctest_main)]
 // ^ This is needed to avoid many warnings because this is not within a separate lib crate.
 
 //! Provides tools for building KSP opendata generators, judges and solvers.
@@ -9,10 +10,13 @@
 //!
 //! You can also use the individual building blocks instead:
 //! * for *generators*, we have [`parse_seed`] to read seeds easily,
-//! * for *solvers*, we provide nothing; they work with stdin/stdout directly,
+//! * for *solvers*, we have [`SolverContext`] with the input, test, seed and deadline,
 //! * for *judges*, we have the [`judge`] module which handles everything for you,
 //! * for all programs, we have [`dataset_dir`] used to get the directory with pre-built datasets.
 //!
+//! For local testing, [`OpenData`] can also run a solver against the judge end-to-end with
+//! `--check <test> <solver> [seed]`, see [`OpenData::handle`].
+//!
 //! # Sharing code between gen, judge, and solve
 //! This module provides a simple way to build a single binary
 //! for any subset of these, which makes it easy to share code
@@ -44,7 +48,7 @@
 //! After setting up the config, the Rust code is as simple as this:
 //! ```rust
 //! use rand::prelude::*;
-//! use opendata::OpenData;
+//! use opendata::{JudgeContext, OpenData, SolverContext};
 //! use opendata::judge::Verdict;
 //!
 //! fn main() {
@@ -62,33 +66,34 @@
 //!     println!("this is an input");
 //! }
 //!
-//! fn judge(
-//!     test_name: &str,                      // Example inputs may have arbitrary names
-//!     seed: Option<u64>,                    // Seed may be None for example inputs
-//!     input_file: Option<File>,             // Only available if enabled in task config
-//!     reference_output_file: Option<File>,  // Only available if enabled in task config
-//! ) -> Verdict {
-//!     // Submitted output is read from stdin.
+//! fn judge(context: JudgeContext) -> Verdict {
+//!     // Submitted output is read from context.submission.
 //!     Verdict::wrong().message("The submitted path is too short.")
 //! }
 //!
-//! fn solve() {
-//!     // Input may be read from stdin.
+//! fn solve(context: SolverContext) {
+//!     // Input may be read from context.input.
 //!     println!("this is a solution");
 //! }
 //!
-//! fn solve2() {
+//! fn solve2(context: SolverContext) {
 //!     println!("this is a different solution");
 //! }
 //! ```
 //!
 
-use crate::opendata::judge::{input_filename, reference_output_filename, Verdict};
-use std::collections::HashMap;
+mod args;
+mod check;
+
+use crate::opendata::judge::{input_filename, reference_output_filename, Verdict, VerdictType};
+use std::collections::BTreeMap;
 use std::env;
 use std::fs::File;
+use std::io::{self, BufRead, Write};
 use std::num::ParseIntError;
 use std::process::exit;
+use std::thread;
+use std::time::{Duration, Instant};
 
 /// Parses a hexadecimal seed for use in generation.
 pub fn parse_seed(hexadecimal: &str) -> Result<u64, ParseIntError> {
@@ -113,22 +118,123 @@ pub fn dataset_dir() -> Option<String> {
 /// ```rust
 /// use opendata::judge::Verdict;
 ///
-/// fn judge(
-///     test_name: &str,
-///     seed: Option<u64>,
-///     input_file: Option<File>,
-///     reference_output_file: Option<File>,
-/// ) -> Verdict {
+/// fn judge(context: JudgeContext) -> Verdict {
 ///     Verdict::wrong().message("The submitted path is too short.")
 /// }
 /// ```
 pub mod judge {
     use std::env;
+    use std::fmt::Write;
     use std::process::exit;
 
+    /// The maximum length of a message in bytes, see [`Verdict::message`].
+    pub const MESSAGE_LIMIT: usize = 255;
+
+    /// Environment variable that selects how verdicts are delivered, see [`Verdict::deliver`].
+    pub const FORMAT_VAR: &str = "VERDICT_FORMAT";
+
+    /// A structured detail of a verdict, only delivered in the JSON format.
+    #[derive(Debug, Clone, PartialEq)]
+    pub enum Detail {
+        Null,
+        Bool(bool),
+        Integer(i64),
+        Number(f64),
+        Text(String),
+        List(Vec<Detail>),
+    }
+
+    impl From<bool> for Detail {
+        fn from(value: bool) -> Self {
+            Detail::Bool(value)
+        }
+    }
+
+    impl From<i64> for Detail {
+        fn from(value: i64) -> Self {
+            Detail::Integer(value)
+        }
+    }
+
+    impl From<usize> for Detail {
+        fn from(value: usize) -> Self {
+            Detail::Integer(value as i64)
+        }
+    }
+
+    impl From<f64> for Detail {
+        fn from(value: f64) -> Self {
+            Detail::Number(value)
+        }
+    }
+
+    impl From<&str> for Detail {
+        fn from(value: &str) -> Self {
+            Detail::Text(value.to_string())
+        }
+    }
+
+    impl From<String> for Detail {
+        fn from(value: String) -> Self {
+            Detail::Text(value)
+        }
+    }
+
+    impl<T: Into<Detail>> From<Option<T>> for Detail {
+        fn from(value: Option<T>) -> Self {
+            value.map_or(Detail::Null, Into::into)
+        }
+    }
+
+    impl<T: Into<Detail>> From<Vec<T>> for Detail {
+        fn from(value: Vec<T>) -> Self {
+            Detail::List(value.into_iter().map(Into::into).collect())
+        }
+    }
+
+    impl Detail {
+        fn write_json(&self, json: &mut String) {
+            match self {
+                Detail::Null => json.push_str("null"),
+                Detail::Bool(value) => json.push_str(if *value { "true" } else { "false" }),
+                Detail::Integer(value) => write!(json, "{}", value).unwrap(),
+                // JSON has no infinities or NaNs
+                Detail::Number(value) if !value.is_finite() => json.push_str("null"),
+                Detail::Number(value) => write!(json, "{:?}", value).unwrap(),
+                Detail::Text(value) => write_json_string(json, value),
+                Detail::List(values) => {
+                    json.push('[');
+                    for (i, value) in values.iter().enumerate() {
+                        if i != 0 {
+                            json.push(',');
+                        }
+                        value.write_json(json);
+                    }
+                    json.push(']');
+                }
+            }
+        }
+    }
+
+    fn write_json_string(json: &mut String, value: &str) {
+        json.push('"');
+        for c in value.chars() {
+            match c {
+                '"' => json.push_str("\\\""),
+                '\\' => json.push_str("\\\\"),
+                '\n' => json.push_str("\\n"),
+                '\r' => json.push_str("\\r"),
+                '\t' => json.push_str("\\t"),
+                c if c < ' ' => write!(json, "\\u{:04x}", c as u32).unwrap(),
+                c => json.push(c),
+            }
+        }
+        json.push('"');
+    }
+
     /// The type of a verdict.
-    #[doc(hidden)]
-    enum VerdictType {
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum VerdictType {
         Correct,
         Wrong,
         InternalError,
@@ -139,7 +245,8 @@ pub mod judge {
     /// # How to use
     /// This is a builder that allows specifying optional extras:
     /// - custom message, shown in the submit interface,
-    /// - custom amount of points.
+    /// - custom amount of points,
+    /// - structured details, only delivered in the JSON format (see [`Verdict::deliver`]).
     ///
     /// This verdict may be used to quit the program by calling [`Verdict::deliver`],
     /// but if you are using [`super::OpenData`], you should instead just return the verdict.
@@ -176,6 +283,7 @@ pub mod judge {
         verdict: VerdictType,
         message: Option<String>,
         points_override: Option<f64>,
+        details: Vec<(String, Detail)>,
     }
 
     impl Verdict {
@@ -185,6 +293,7 @@ pub mod judge {
                 verdict,
                 message: None,
                 points_override: None,
+                details: Vec::new(),
             }
         }
 
@@ -207,7 +316,7 @@ pub mod judge {
         }
 
         /// Sets the message that appears in the submit interface.
-        /// The message should be a maximum of 255 bytes.
+        /// The message should be a maximum of [`MESSAGE_LIMIT`] bytes.
         #[must_use]
         pub fn message(mut self, message: &str) -> Self {
             self.message = Some(message.to_string());
@@ -224,8 +333,74 @@ pub mod judge {
             self
         }
 
+        /// Adds a structured detail, for example the number of reached goals.
+        /// Details are only delivered in the JSON format, the submit interface never shows them.
+        #[must_use]
+        pub fn detail(mut self, key: &str, value: impl Into<Detail>) -> Self {
+            self.details.push((key.to_string(), value.into()));
+            self
+        }
+
+        /// Returns the type of this verdict.
+        pub fn verdict_type(&self) -> VerdictType {
+            self.verdict
+        }
+
+        /// Returns the message set by [`Verdict::message`], if any.
+        pub fn get_message(&self) -> Option<&str> {
+            self.message.as_deref()
+        }
+
+        /// Returns the points set by [`Verdict::override_points`], if any.
+        pub fn get_points(&self) -> Option<f64> {
+            self.points_override
+        }
+
+        /// Returns the details added by [`Verdict::detail`], in the order they were added.
+        pub fn get_details(&self) -> &[(String, Detail)] {
+            &self.details
+        }
+
+        /// Formats the verdict as a single line JSON object, for example
+        /// `{"verdict":"correct","points":12.0,"message":"...","details":{"ticks":123}}`.
+        ///
+        /// Missing points and message are `null`.
+        pub fn to_json(&self) -> String {
+            let verdict = match self.verdict {
+                VerdictType::Correct => "correct",
+                VerdictType::Wrong => "wrong",
+                VerdictType::InternalError => "internal_error",
+            };
+
+            let mut json = String::new();
+
+            json.push_str("{\"verdict\":");
+            write_json_string(&mut json, verdict);
+            json.push_str(",\"points\":");
+            Detail::from(self.points_override).write_json(&mut json);
+            json.push_str(",\"message\":");
+            Detail::from(self.message.clone()).write_json(&mut json);
+            json.push_str(",\"details\":{");
+            for (i, (key, value)) in self.details.iter().enumerate() {
+                if i != 0 {
+                    json.push(',');
+                }
+                write_json_string(&mut json, key);
+                json.push(':');
+                value.write_json(&mut json);
+            }
+            json.push_str("}}");
+
+            json
+        }
+
         /// Delivers the verdict, **ending the program**.
         ///
+        /// By default, this follows the KSP protocol: the message and points are written
+        /// to stderr and the exit code is 42 for correct, 43 for wrong and 1 for internal errors.
+        /// With [`FORMAT_VAR`] set to `json`, the verdict is written to stdout as
+        /// [`Verdict::to_json`] instead; the exit codes stay the same.
+        ///
         /// Do not use directly if you are using [`super::OpenData`], return the verdict instead.
         pub fn deliver(self) {
             let exit_code = match self.verdict {
@@ -234,6 +409,11 @@ pub mod judge {
                 VerdictType::InternalError => 1,
             };
 
+            if json_format() {
+                println!("{}", self.to_json());
+                exit(exit_code);
+            }
+
             let mut newline_printed = false;
             if let Some(message) = self.message {
                 eprintln!("{}", message);
@@ -251,6 +431,11 @@ pub mod judge {
         }
     }
 
+    /// Whether verdicts should be delivered as JSON, see [`FORMAT_VAR`].
+    pub fn json_format() -> bool {
+        env::var(FORMAT_VAR).is_ok_and(|format| format.eq_ignore_ascii_case("json"))
+    }
+
     /// Returns the filename of the input file - this is the file that contestants get.
     /// Requires `judge_needs_in=1` in the task config, otherwise `None` is returned.
     pub fn input_filename() -> Option<String> {
@@ -265,9 +450,183 @@ pub mod judge {
     }
 }
 
-type GeneratorHandler = fn(usize, u64);
-type JudgeHandler = fn(&str, Option<u64>, Option<File>, Option<File>) -> Verdict;
-type SolverHandler = fn();
+type GeneratorHandler = Box<dyn Fn(usize, u64)>;
+type JudgeHandler = Box<dyn Fn(JudgeContext) -> Verdict>;
+type SolverHandler = Box<dyn Fn(SolverContext)>;
+
+/// A solver added by [`OpenData::add_solver`], with an optional description for `--help`.
+struct NamedSolver {
+    description: Option<String>,
+    handler: SolverHandler,
+}
+
+/// The signature of judges before [`JudgeContext`] existed, see [`legacy_judge`].
+pub type LegacyJudgeHandler = fn(&str, Option<u64>, Option<File>, Option<File>) -> Verdict;
+
+/// The signature of solvers before [`SolverContext`] existed, see [`legacy_solver`].
+pub type LegacySolverHandler = fn();
+
+/// Everything a judge gets to decide about a submission.
+pub struct JudgeContext {
+    /// Name of the subtask, usually an unsigned that may be parsed, but for example inputs,
+    /// this will be the name of the input.
+    pub test_name: String,
+    /// The seed used to generate the task. `None` for example inputs, as they do not have seeds.
+    pub seed: Option<u64>,
+    /// The input file; only available if enabled in task config (`judge_needs_in=1`).
+    pub input_file: Option<File>,
+    /// The name of the input file, see [`judge::input_filename`].
+    pub input_filename: Option<String>,
+    /// The reference output file generated by our solver; only available if enabled in task
+    /// config (`judge_needs_out=1`).
+    pub reference_output_file: Option<File>,
+    /// The directory with pre-built datasets, see [`dataset_dir`].
+    pub dataset_dir: Option<String>,
+    /// The judged output; stdin when running as a judge.
+    pub submission: Box<dyn BufRead>,
+}
+
+impl JudgeContext {
+    /// Creates the context from the environment set up by opendata, reading the submission
+    /// from stdin.
+    ///
+    /// # Errors
+    /// Fails if the input or reference output file is set but can't be opened.
+    pub fn from_env(test_name: &str, seed: Option<u64>) -> io::Result<Self> {
+        let input_filename = input_filename();
+
+        Ok(JudgeContext {
+            test_name: test_name.to_string(),
+            seed,
+            input_file: input_filename.as_ref().map(File::open).transpose()?,
+            input_filename,
+            reference_output_file: reference_output_filename().map(File::open).transpose()?,
+            dataset_dir: dataset_dir(),
+            submission: Box::new(io::stdin().lock()),
+        })
+    }
+}
+
+/// Environment variable with the wall-clock time limit for solvers, in seconds.
+pub const SOLVER_TIME_LIMIT_VAR: &str = "SOLVER_TIME_LIMIT";
+
+/// How long a solver may run after its deadline before it is killed,
+/// so it has time to print its best answer.
+pub const GRACE_PERIOD: Duration = Duration::from_secs(1);
+
+/// Exit code of solvers killed for running out of time, the same as `timeout` uses.
+pub const TIME_LIMIT_EXIT_CODE: i32 = 124;
+
+/// Everything a solver gets to solve a test.
+pub struct SolverContext {
+    /// Name of the test, if it was given on the command line (`<solver> [test_id] [seed]`).
+    pub test_name: Option<String>,
+    /// The seed the test was generated with; `None` if not given or for example inputs.
+    pub seed: Option<u64>,
+    /// The input of the test; stdin when running as a solver.
+    pub input: Box<dyn BufRead>,
+    /// When the solver should print its answer and stop, see [`OpenData::solver_time_limit`].
+    /// `None` without a time limit.
+    pub deadline: Option<Instant>,
+}
+
+impl SolverContext {
+    /// Time left until the deadline, `None` without a time limit.
+    pub fn time_left(&self) -> Option<Duration> {
+        (self.deadline).map(|deadline| deadline.saturating_duration_since(Instant::now()))
+    }
+
+    /// Whether the deadline has passed; anytime solvers should print their best answer then.
+    pub fn out_of_time(&self) -> bool {
+        self.deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
+    }
+}
+
+/// Returns the solver time limit set by [`SOLVER_TIME_LIMIT_VAR`], if any.
+///
+/// # Errors
+/// Fails if the time limit is not a non-negative number of seconds.
+pub fn solver_time_limit() -> Result<Option<Duration>, String> {
+    match env::var(SOLVER_TIME_LIMIT_VAR) {
+        Ok(limit) => parse_time_limit(&limit).map(Some).ok_or_else(|| {
+            format!(
+                "{}={} is not a number of seconds",
+                SOLVER_TIME_LIMIT_VAR, limit
+            )
+        }),
+        Err(_) => Ok(None),
+    }
+}
+
+fn parse_time_limit(seconds: &str) -> Option<Duration> {
+    (seconds.trim().parse().ok()).and_then(|seconds| Duration::try_from_secs_f64(seconds).ok())
+}
+
+/// Adapts a judge with the old four argument signature to [`OpenData::add_judge`].
+///
+/// The submission is still read from stdin by the legacy judge itself.
+///
+/// # Example
+/// ```rust
+/// fn main() {
+///     OpenData::new()
+///         .add_judge(legacy_judge(judge))
+///         .handle();
+/// }
+///
+/// fn judge(
+///     test_name: &str,
+///     seed: Option<u64>,
+///     input_file: Option<File>,
+///     reference_output_file: Option<File>,
+/// ) -> Verdict {
+///     Verdict::wrong().message("The submitted path is too short.")
+/// }
+/// ```
+pub fn legacy_judge(f: LegacyJudgeHandler) -> impl Fn(JudgeContext) -> Verdict {
+    move |context| {
+        let JudgeContext {
+            test_name,
+            seed,
+            input_file,
+            reference_output_file,
+            submission,
+            ..
+        } = context;
+
+        // the legacy judge locks stdin itself
+        drop(submission);
+
+        f(&test_name, seed, input_file, reference_output_file)
+    }
+}
+
+/// Adapts a solver without arguments to [`OpenData::add_solver`].
+///
+/// The input is still read from stdin by the legacy solver itself, and it doesn't get the
+/// deadline.
+///
+/// # Example
+/// ```rust
+/// fn main() {
+///     OpenData::new()
+///         .add_solver("--solve", legacy_solver(solve))
+///         .handle();
+/// }
+///
+/// fn solve() {
+///     println!("this is a solution");
+/// }
+/// ```
+pub fn legacy_solver(f: LegacySolverHandler) -> impl Fn(SolverContext) {
+    move |context| {
+        // the legacy solver locks stdin itself
+        drop(context);
+
+        f()
+    }
+}
 
 /// Builder for a handler that calls the correct subprogram.
 ///
@@ -279,7 +638,7 @@ type SolverHandler = fn();
 /// ## Generator + Judge + Solver
 /// ```rust
 /// use rand::prelude::*;
-/// use opendata::OpenData;
+/// use opendata::{JudgeContext, OpenData, SolverContext};
 /// use opendata::judge::Verdict;
 ///
 /// fn main() {
@@ -297,29 +656,26 @@ type SolverHandler = fn();
 ///     println!("this is an input");
 /// }
 ///
-/// fn judge(
-///     test_name: &str,                      // Example inputs may have arbitrary names
-///     seed: Option<u64>,                    // Seed may be None for example inputs
-///     input_file: Option<File>,             // Only available if enabled in task config
-///     reference_output_file: Option<File>,  // Only available if enabled in task config
-/// ) -> Verdict {
-///     // Submitted output is read from stdin.
+/// fn judge(context: JudgeContext) -> Verdict {
+///     // Submitted output is read from context.submission.
 ///     Verdict::wrong().message("The submitted path is too short.")
 /// }
 ///
-/// fn solve() {
-///     // Input may be read from stdin.
+/// fn solve(context: SolverContext) {
+///     // Input may be read from context.input.
 ///     println!("this is a solution");
 /// }
 ///
-/// fn solve2() {
+/// fn solve2(context: SolverContext) {
 ///     println!("this is a different solution");
 /// }
 /// ```
 pub struct OpenData {
     generate_handler: Option<GeneratorHandler>,
     judge_handler: Option<JudgeHandler>,
-    solve_handlers: HashMap<String, SolverHandler>,
+    solve_handlers: BTreeMap<String, NamedSolver>,
+    solver_time_limit: Option<Duration>,
+    test_count: Option<usize>,
 }
 
 impl OpenData {
@@ -328,7 +684,9 @@ impl OpenData {
         OpenData {
             generate_handler: None,
             judge_handler: None,
-            solve_handlers: HashMap::new(),
+            solve_handlers: BTreeMap::new(),
+            solver_time_limit: None,
+            test_count: None,
         }
     }
 
@@ -344,10 +702,15 @@ impl OpenData {
     /// smaller tests to be substrings of bigger tests. This won't happen when ran
     /// by contestants, as a separate seed is used for each test case.
     ///
+    /// The generator may be any closure, so it can capture whatever it needs.
+    ///
     /// ## Generator function arguments
     /// - `test:` [`usize`] &ndash; number of the subtask, **1-indexed**,
     /// - `seed:` [`u64`] &ndash; value that **must** be used to seed any random number generation.
     ///
+    /// Only test numbers from 1 to the [`OpenData::test_count`] are passed to the generator,
+    /// if it is set.
+    ///
     /// # Example generator
     ///
     /// ```rust
@@ -366,9 +729,9 @@ impl OpenData {
     /// }
     /// ```
     #[must_use]
-    pub fn add_generator(mut self, f: GeneratorHandler) -> Self {
+    pub fn add_generator(mut self, f: impl Fn(usize, u64) + 'static) -> Self {
         assert!(self.generate_handler.is_none());
-        self.generate_handler = Some(f);
+        self.generate_handler = Some(Box::new(f));
         self
     }
 
@@ -385,13 +748,12 @@ impl OpenData {
     /// usually many of those.
     ///
     /// # Judge function arguments
-    /// * `test_name:` [`&str`] &ndash; name of the subtask, usually an unsigned that may be parsed, but for example inputs, this will be the name of the input.
-    /// * `seed:` [`Option<u64>`] &ndash; the seed used to generate the task. May be `None` in case this is an example input, as they do not have seeds.
-    /// * `input_file:` [`Option<File>`] &ndash; the input file; only available if enabled in task config (`judge_needs_in=1`), otherwise `None`.
-    /// * `reference_output_file`: [`Option<File>`] &ndash; the reference output file generated by our solver; only available if enabled in task config (`judge_needs_out=1`), otherwise `None`.
+    /// The judge gets a [`JudgeContext`] with the test name, seed, input and reference output
+    /// files and the dataset directory. Judges with the old four argument signature can be
+    /// adapted with [`legacy_judge`].
     ///
     /// # Judged output
-    /// The judged output is read from stdin.
+    /// The judged output is read from [`JudgeContext::submission`], which is stdin.
     ///
     /// # Judge return value
     /// The judge function returns a [`judge::Verdict`]. Make sure to read the documentation for that type to
@@ -410,26 +772,21 @@ impl OpenData {
     ///         .handle();
     /// }
     ///
-    /// fn judge(
-    ///     test_name: &str,                      // Examples may have arbitrary names
-    ///     seed: Option<u64>,                    // Seed may be None for example inputs
-    ///     input_file: Option<File>,             // Only available if enabled in task config
-    ///     reference_output_file: Option<File>,  // Only available if enabled in task config
-    /// ) -> Verdict {
+    /// fn judge(context: JudgeContext) -> Verdict {
     ///     Verdict::wrong().message("The submitted path is too short.")
     /// }
     /// ```
     #[must_use]
-    pub fn add_judge(mut self, f: JudgeHandler) -> Self {
+    pub fn add_judge(mut self, f: impl Fn(JudgeContext) -> Verdict + 'static) -> Self {
         assert!(self.judge_handler.is_none());
-        self.judge_handler = Some(f);
+        self.judge_handler = Some(Box::new(f));
         self
     }
 
     /// Allows this program to act as a solver.
     ///
     /// # About solvers
-    /// Read the stdin, solve the task, write to stdout. It is as simple as that.
+    /// Read the input, solve the task, write to stdout. It is as simple as that.
     ///
     /// # Multiple solvers
     /// Multiple solvers with different names may be defined, for example `--solve`, `--solve-slow`.
@@ -452,11 +809,14 @@ impl OpenData {
     /// Please use `--solve` as the name of the main solver.
     ///
     /// # Solver function arguments
-    /// The solver has no arguments, the input is read from stdin.
+    /// The solver gets a [`SolverContext`] with the input (stdin), the test name and seed
+    /// (if given as `<solver> [test_id] [seed]`) and the deadline, if there is a time limit
+    /// (see [`OpenData::solver_time_limit`]). Solvers without arguments can be adapted with
+    /// [`legacy_solver`].
     ///
     /// # Example solvers
     /// ```rust
-    /// use opendata::OpenData;
+    /// use opendata::{OpenData, SolverContext};
     ///
     /// fn main() {
     ///     OpenData::new()
@@ -465,60 +825,143 @@ impl OpenData {
     ///         .handle();
     /// }
     ///
-    /// fn solve() {
+    /// fn solve(mut context: SolverContext) {
     ///     let mut bytes: Vec<u8> = Vec::new();
-    ///     std::io::stdin().read_to_end(&mut bytes);
+    ///     context.input.read_to_end(&mut bytes);
     ///     for byte in bytes.iter().rev() {
     ///         print!("{}", byte.to_ascii_lowercase())
     ///     }
     /// }
     ///
-    /// fn solve2() {
+    /// fn solve2(mut context: SolverContext) {
     ///     let mut string = String::new();
-    ///     std::io::stdin().read_to_string(&mut string);
+    ///     context.input.read_to_string(&mut string);
     ///     println!("{}", string.chars().rev().collect::<String>());
     /// }
     /// ```
     #[must_use]
-    pub fn add_solver(mut self, name: &str, f: SolverHandler) -> Self {
-        assert_ne!(name, "--judge");
-        assert_ne!(name, "--gen");
-        assert!(!self.solve_handlers.contains_key(name));
-        self.solve_handlers.insert(name.to_string(), f);
+    pub fn add_solver(self, name: &str, f: impl Fn(SolverContext) + 'static) -> Self {
+        self.insert_solver(name, None, f)
+    }
+
+    /// Allows this program to act as a solver, like [`OpenData::add_solver`],
+    /// with a description shown by `--help` and `--list`.
+    ///
+    /// # Example
+    /// ```rust
+    /// fn main() {
+    ///     OpenData::new()
+    ///         .add_solver_with_description("--solve-empty", "Submits no instructions.", |_| {
+    ///             println!("0")
+    ///         })
+    ///         .handle();
+    /// }
+    /// ```
+    #[must_use]
+    pub fn add_solver_with_description(
+        self,
+        name: &str,
+        description: &str,
+        f: impl Fn(SolverContext) + 'static,
+    ) -> Self {
+        self.insert_solver(name, Some(description), f)
+    }
+
+    /// Limits the wall-clock time of all solvers.
+    ///
+    /// Solvers get the deadline in [`SolverContext::deadline`], so anytime solvers can stop
+    /// and print their best answer. A solver still running [`GRACE_PERIOD`] after the deadline
+    /// is killed with exit code [`TIME_LIMIT_EXIT_CODE`]. Its output printed so far is flushed
+    /// first (waiting for a print in progress), but an answer printed later is lost.
+    ///
+    /// The limit may be overridden by [`SOLVER_TIME_LIMIT_VAR`], for example to give solvers
+    /// more time when generating reference outputs.
+    ///
+    /// # Example
+    /// ```rust
+    /// fn main() {
+    ///     OpenData::new()
+    ///         .add_solver("--solve", solve)
+    ///         .solver_time_limit(Duration::from_secs(10))
+    ///         .handle();
+    /// }
+    ///
+    /// fn solve(context: SolverContext) {
+    ///     let mut best = initial_solution(context.input);
+    ///     while !context.out_of_time() {
+    ///         best = improve(best);
+    ///     }
+    ///     println!("{}", best);
+    /// }
+    /// ```
+    #[must_use]
+    pub fn solver_time_limit(mut self, limit: Duration) -> Self {
+        self.solver_time_limit = Some(limit);
+        self
+    }
+
+    /// Sets the number of tests, so that test numbers other than 1 to `count` are rejected
+    /// when parsing the arguments of the generator and `--check`.
+    #[must_use]
+    pub fn test_count(mut self, count: usize) -> Self {
+        self.test_count = Some(count);
         self
     }
 
     #[doc(hidden)]
-    fn print_usage(&self, args: &[String]) {
-        let empty = self.generate_handler.is_none()
-            && self.judge_handler.is_none()
-            && self.solve_handlers.is_empty();
-        if empty {
-            println!("Invalid arguments.");
-            println!("This program does nothing because no handlers were added; make sure to read the documentation.");
-        } else {
-            println!("Invalid arguments.");
-            println!("This is a single binary for multiple programs, see usage:");
-            if self.generate_handler.is_some() {
-                println!("\tGenerator: {} --gen <test_id> <seed>", args[0]);
-            }
-            if self.judge_handler.is_some() {
-                println!("\tJudge:     {} --judge <test_id> <seed>", args[0]);
-            }
-            for solve_handler in &self.solve_handlers {
-                println!("\tSolver:    {} {}", args[0], solve_handler.0);
-            }
+    fn insert_solver(
+        mut self,
+        name: &str,
+        description: Option<&str>,
+        f: impl Fn(SolverContext) + 'static,
+    ) -> Self {
+        for reserved in ["--gen", "--judge", "--check", "--help", "-h", "--list"] {
+            assert_ne!(name, reserved);
         }
+        assert!(!self.solve_handlers.contains_key(name));
+
+        let solver = NamedSolver {
+            description: description.map(str::to_string),
+            handler: Box::new(f),
+        };
+
+        self.solve_handlers.insert(name.to_string(), solver);
+        self
     }
 
     #[doc(hidden)]
-    fn print_usage_and_exit(&self, args: &[String]) {
-        self.print_usage(args);
-        exit(1);
+    fn check(
+        &self,
+        judge: &JudgeHandler,
+        test: &check::TestInput,
+        solver: &check::Solver,
+        generate: &check::Generate,
+    ) -> io::Result<Verdict> {
+        if let check::TestInput::Generated(..) = test {
+            if self.generate_handler.is_none() {
+                return Err(io::Error::other("no generator was added"));
+            }
+        }
+
+        let input = check::Input::prepare(test, generate)?;
+        let output = solver.run(test, input.path())?;
+
+        Ok(judge(input.judge_context(test, output)?))
     }
 
     /// Choose a subprogram depending on arguments and run it. Exits the program afterwards.
     ///
+    /// Besides the added programs, `--check <test> <solver> [seed]` runs the solver on the test
+    /// and prints what the judge thinks of its output. The test is a test number (the input is
+    /// generated with the given seed, `0` by default) or an input file. The solver is the name
+    /// of an added solver, an executable, a file with the output, or a shell command.
+    /// The exit code is `0` only for correct outputs.
+    ///
+    /// `--help [program]` shows the usage of all programs or of one of them (as does
+    /// `<program> --help`) and `--list` lists the added programs with their descriptions.
+    ///
+    /// Invalid arguments are reported with the usage and exit code `2`, nothing panics.
+    ///
     /// This finishes the [`OpenData`] configuration,
     /// Generally, this should be the last thing ran in the `main` function.
     ///
@@ -534,58 +977,206 @@ impl OpenData {
     /// ```
     pub fn handle(self) {
         let args: Vec<String> = std::env::args().collect();
-        if args.len() < 2 {
-            self.print_usage_and_exit(&args);
-        }
-        match args[1].as_str() {
-            "--gen" => {
-                if let Some(ref gen) = self.generate_handler {
-                    if args.len() < 4 {
-                        self.print_usage_and_exit(&args);
-                    }
-                    let (test_number, seed): (usize, u64) = (
-                        args[2].parse().expect("Test number has to be an integer"),
-                        parse_seed(&args[3]).expect("The seed format is incorrect"),
-                    );
+        let binary = args.first().map_or("opendata", String::as_str);
 
-                    gen(test_number, seed);
-                    exit(0);
-                }
+        let action = match args::parse(&self, args.get(1..).unwrap_or_default()) {
+            Ok(action) => action,
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                eprintln!();
+                eprint!("{}", args::usage(&self, binary, None));
+                exit(args::USAGE_EXIT_CODE);
             }
-            "--judge" => {
-                if let Some(ref judge) = self.judge_handler {
-                    if args.len() < 4 {
-                        self.print_usage_and_exit(&args);
-                    }
-                    let test_name = &args[2];
-                    let seed: Option<u64> = match args[3].as_str() {
-                        "-" => None,
-                        hexadecimal => {
-                            Some(parse_seed(hexadecimal).expect("The seed format is incorrect"))
+        };
+
+        match action {
+            args::Action::Generate(test_number, seed) => {
+                let generate = self.generate_handler.as_ref().unwrap();
+
+                generate(test_number, seed);
+                exit(0);
+            }
+            args::Action::Judge(test_name, seed) => {
+                let judge = self.judge_handler.as_ref().unwrap();
+
+                let verdict = match JudgeContext::from_env(&test_name, seed) {
+                    Ok(context) => judge(context),
+                    Err(e) => Verdict::internal_error()
+                        .message(&format!("Could not open the input or output file: {}", e)),
+                };
+
+                verdict.deliver();
+            }
+            args::Action::Check(test, solver, seed) => {
+                let judge = self.judge_handler.as_ref().unwrap();
+
+                let test = check::TestInput::parse(&test, seed);
+                let solver = check::Solver::parse(&solver, self.solve_handlers.keys());
+
+                match self.check(judge, &test, &solver, &check::run_generator) {
+                    Ok(verdict) => {
+                        if judge::json_format() {
+                            println!("{}", verdict.to_json());
+                        } else {
+                            println!("Verdict: {:?}", verdict.verdict_type());
+                            if let Some(points) = verdict.get_points() {
+                                println!("Points:  {}", points);
+                            }
+                            if let Some(message) = verdict.get_message() {
+                                println!("Message: {}", message);
+                            }
                         }
-                    };
-
-                    let input_name = input_filename();
-                    let output_name = reference_output_filename();
-                    let input_file =
-                        input_name.map(|name| File::open(name).expect("Could not open input file"));
-                    let reference_output_file = output_name
-                        .map(|name| File::open(name).expect("Could not open output file"));
-
-                    let verdict = judge(test_name, seed, input_file, reference_output_file);
-                    verdict.deliver();
-                } else {
-                    self.print_usage_and_exit(&args);
+
+                        exit(match verdict.verdict_type() {
+                            VerdictType::Correct => 0,
+                            _ => 1,
+                        });
+                    }
+                    Err(e) => {
+                        eprintln!("Check failed: {}", e);
+                        exit(1);
+                    }
                 }
             }
-            other => {
-                if let Some(solve) = self.solve_handlers.get(other) {
-                    solve();
-                    exit(0);
-                } else {
-                    self.print_usage_and_exit(&args);
+            args::Action::Solve(name, test_name, seed) => {
+                let time_limit = match solver_time_limit() {
+                    Ok(limit) => limit.or(self.solver_time_limit),
+                    Err(e) => {
+                        eprintln!("Error: {}", e);
+                        exit(args::USAGE_EXIT_CODE);
+                    }
+                };
+
+                let context = SolverContext {
+                    test_name,
+                    seed,
+                    input: Box::new(io::stdin().lock()),
+                    deadline: time_limit.map(|limit| Instant::now() + limit),
+                };
+
+                if let Some(limit) = time_limit {
+                    // the solver had its chance to stop gracefully
+                    thread::spawn(move || {
+                        thread::sleep(limit + GRACE_PERIOD);
+                        eprintln!("Time limit of {:?} exceeded.", limit);
+
+                        // keep the solver from printing while exiting
+                        let mut stdout = io::stdout().lock();
+                        stdout.flush().ok();
+                        exit(TIME_LIMIT_EXIT_CODE);
+                    });
                 }
+
+                (self.solve_handlers[&name].handler)(context);
+                exit(0);
+            }
+            args::Action::Help(program) => {
+                print!("{}", args::usage(&self, binary, program.as_ref()));
+                exit(0);
+            }
+            args::Action::List => {
+                print!("{}", args::list(&self));
+                exit(0);
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::judge::*;
+    use super::*;
+    use serde_json::{json, Value};
+    use std::fs;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_verdict_json() {
+        let verdict = Verdict::correct()
+            .override_points(4.5)
+            .message("Line \"1\"\n\tand a \\ \u{1}")
+            .detail("ticks", 120usize)
+            .detail("finished_tick", None::<usize>)
+            .detail("goal_ticks", vec![Some(3usize), None])
+            .detail("infinite", f64::INFINITY);
+
+        let json: Value = serde_json::from_str(&verdict.to_json()).unwrap();
+
+        assert_eq!(
+            json,
+            json!({
+                "verdict": "correct",
+                "points": 4.5,
+                "message": "Line \"1\"\n\tand a \\ \u{1}",
+                "details": {
+                    "ticks": 120,
+                    "finished_tick": null,
+                    "goal_ticks": [3, null],
+                    "infinite": null,
+                },
+            })
+        );
+
+        let json: Value = serde_json::from_str(&Verdict::internal_error().to_json()).unwrap();
+
+        assert_eq!(
+            json,
+            json!({"verdict": "internal_error", "points": null, "message": null, "details": {}})
+        );
+    }
+
+    #[test]
+    fn test_solver_deadline() {
+        let context = |deadline| SolverContext {
+            test_name: None,
+            seed: None,
+            input: Box::new("".as_bytes()),
+            deadline,
+        };
+
+        let unlimited = context(None);
+        assert_eq!(unlimited.time_left(), None);
+        assert!(!unlimited.out_of_time());
+
+        let future = context(Some(Instant::now() + Duration::from_secs(3600)));
+        assert!(future.time_left().unwrap() > Duration::from_secs(3000));
+        assert!(!future.out_of_time());
+
+        let past = context(Some(Instant::now()));
+        assert_eq!(past.time_left(), Some(Duration::ZERO));
+        assert!(past.out_of_time());
+    }
+
+    #[test]
+    fn test_check_generated() {
+        let opendata = OpenData::new()
+            .add_generator(crate::generator::generate)
+            .test_count(crate::generator::TESTS.len())
+            .add_judge(crate::judge::judge);
+        let judge = opendata.judge_handler.as_ref().unwrap();
+
+        // the shipped sprint map stands in for the generated one, so the sample solution finishes
+        let generate = |test_number, _| {
+            assert_eq!(test_number, 1);
+            fs::read("../../maps/sprint.txt")
+        };
+        let test = check::TestInput::Generated(1, 0);
+        let solver = check::Solver::Output(PathBuf::from("../../test/solves/sprint.in"));
+
+        let verdict = opendata.check(judge, &test, &solver, &generate).unwrap();
+
+        // judged as a sprint, where the sample solution is slower than the baseline
+        assert_eq!(verdict.verdict_type(), VerdictType::Correct);
+        let points = verdict.get_points().unwrap();
+        assert!(points > 0.0 && points < 12.0, "{}", points);
+    }
+
+    #[test]
+    fn test_parse_time_limit() {
+        assert_eq!(parse_time_limit("10"), Some(Duration::from_secs(10)));
+        assert_eq!(parse_time_limit(" 0.5\n"), Some(Duration::from_millis(500)));
+        assert_eq!(parse_time_limit("-1"), None);
+        assert_eq!(parse_time_limit("ten"), None);
+        assert_eq!(parse_time_limit("inf"), None);
+    }
+}
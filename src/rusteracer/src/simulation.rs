@@ -1,10 +1,15 @@
+use crate::grid::Grid;
 use rand::prelude::*;
-use std::collections::HashMap;
-use std::fs;
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+use std::fmt;
 use std::fs::File;
-use std::io::Write;
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
 use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Arc;
 
+#[allow(non_snake_case)]
 pub mod TickFlag {
     pub const COLLIDED: usize = 1;
     pub const GOAL_REACHED: usize = 2;
@@ -25,7 +30,156 @@ pub static MAX_COLLISION_RESOLUTIONS: usize = 5;
 
 pub static CELL_SIZE: PosType = 10_000;
 
+/// The constants of the simulation's physics, which may be overridden per map.
+///
+/// The defaults are the values of the official maps (see SPECIFICATION.md).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PhysicsConfig {
+    pub max_acceleration: InstType,
+    pub drag_fraction: (SpeedType, SpeedType),
+    pub collision_fraction: (SpeedType, SpeedType),
+    pub max_collision_resolutions: usize,
+    pub cell_size: PosType,
+}
+
+impl Default for PhysicsConfig {
+    fn default() -> Self {
+        Self {
+            max_acceleration: MAX_ACCELERATION,
+            drag_fraction: DRAG_FRACTION,
+            collision_fraction: COLLISION_FRACTION,
+            max_collision_resolutions: MAX_COLLISION_RESOLUTIONS,
+            cell_size: CELL_SIZE,
+        }
+    }
+}
+
+impl PhysicsConfig {
+    /// Whether the instruction is no longer than the maximum acceleration.
+    pub fn allows(&self, instruction: Instruction) -> bool {
+        Instruction::valid(
+            instruction.vx.into(),
+            instruction.vy.into(),
+            self.max_acceleration,
+        )
+    }
+
+    /// An instruction in the direction of `vx`/`vy`, shortened to the maximum acceleration
+    /// if it's longer.
+    pub fn instruction<T>(&self, vx: T, vy: T) -> Instruction
+    where
+        T: Copy + Into<PosType>,
+    {
+        let max_acceleration = self.max_acceleration;
+
+        let vx: i64 = vx.into();
+        let vy: i64 = vy.into();
+
+        if !Instruction::valid(vx, vy, max_acceleration) {
+            // use float to properly normalize here
+            let float_distance = ((vx as f64).powf(2.) + (vy as f64).powf(2.)).powf(1. / 2.);
+
+            let mut vx = ((vx as f64 / float_distance) * max_acceleration as f64) as PosType;
+            let mut vy = ((vy as f64 / float_distance) * max_acceleration as f64) as PosType;
+
+            // if we're still over, decrement both values
+            if !Instruction::valid(vx, vy, max_acceleration) {
+                vx -= vx.signum();
+                vy -= vy.signum();
+            }
+
+            return Instruction {
+                vx: vx as InstType,
+                vy: vy as InstType,
+            };
+        }
+
+        assert!(Instruction::valid(vx, vy, max_acceleration));
+
+        Instruction {
+            vx: vx as InstType,
+            vy: vy as InstType,
+        }
+    }
+
+    /// A random instruction no longer than the maximum acceleration.
+    pub fn random_instruction(&self) -> Instruction {
+        let mut rng = rand::rng();
+
+        self.instruction(
+            rng.random_range(-self.max_acceleration..=self.max_acceleration),
+            rng.random_range(-self.max_acceleration..=self.max_acceleration),
+        )
+    }
+
+    /// Parse the optional map header overriding some of the constants, e.g.
+    /// `physics max_acceleration=100 drag=19/20 collision=3/4 max_collision_resolutions=3`.
+    /// Returns the name of the invalid setting on error.
+    fn from_header(header: &str) -> Result<Self, String> {
+        fn fraction(value: &str) -> Option<(SpeedType, SpeedType)> {
+            let (numerator, denominator) = value.split_once('/')?;
+            let fraction = (numerator.parse().ok()?, denominator.parse().ok()?);
+
+            // the numerator may be zero, but the velocity must not grow or flip
+            (fraction.1 > 0 && (0..=fraction.1).contains(&fraction.0)).then_some(fraction)
+        }
+
+        let mut physics = Self::default();
+
+        for setting in header.split_whitespace().skip(1) {
+            let (key, value) = setting.split_once('=').ok_or(setting)?;
+
+            match key {
+                "max_acceleration" => {
+                    physics.max_acceleration = value.parse().ok().filter(|&v| v >= 0).ok_or(key)?
+                }
+                "drag" => physics.drag_fraction = fraction(value).ok_or(key)?,
+                "collision" => physics.collision_fraction = fraction(value).ok_or(key)?,
+                "max_collision_resolutions" => {
+                    physics.max_collision_resolutions = value.parse().ok().ok_or(key)?
+                }
+                "cell_size" => {
+                    physics.cell_size = value.parse().ok().filter(|&v| v > 0).ok_or(key)?
+                }
+                _ => return Err(key.to_string()),
+            }
+        }
+
+        Ok(physics)
+    }
+
+    /// The map header with the constants that differ from the defaults.
+    fn to_header(self) -> Option<String> {
+        let default = Self::default();
+        let mut header = String::from("physics");
+
+        if self.max_acceleration != default.max_acceleration {
+            header += &format!(" max_acceleration={}", self.max_acceleration);
+        }
+        if self.drag_fraction != default.drag_fraction {
+            header += &format!(" drag={}/{}", self.drag_fraction.0, self.drag_fraction.1);
+        }
+        if self.collision_fraction != default.collision_fraction {
+            let (numerator, denominator) = self.collision_fraction;
+            header += &format!(" collision={}/{}", numerator, denominator);
+        }
+        if self.max_collision_resolutions != default.max_collision_resolutions {
+            header += &format!(
+                " max_collision_resolutions={}",
+                self.max_collision_resolutions
+            );
+        }
+        if self.cell_size != default.cell_size {
+            header += &format!(" cell_size={}", self.cell_size);
+        }
+
+        (self != default).then_some(header)
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Racer {
     pub x: PosType,
     pub y: PosType,
@@ -35,6 +189,7 @@ pub struct Racer {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Asteroid {
     pub x: PosType,
     pub y: PosType,
@@ -44,91 +199,189 @@ pub struct Asteroid {
 pub type Goal = Asteroid;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub(crate) struct Instruction {
     pub vx: InstType,
     pub vy: InstType,
 }
 
 impl Instruction {
-    fn valid(vx: PosType, vy: PosType) -> bool {
-        distance_squared(vx, vy, 0, 0) <= (MAX_ACCELERATION as PosType).pow(2)
+    fn valid(vx: PosType, vy: PosType, max_acceleration: InstType) -> bool {
+        distance_squared(vx, vy, 0, 0) <= (max_acceleration as u128).pow(2)
     }
 
+    /// An instruction for the default physics, see [`PhysicsConfig::instruction`].
+    #[allow(dead_code)]
     pub fn new<T>(vx: T, vy: T) -> Self
     where
         T: Copy + Into<PosType>,
     {
-        let vx: i64 = vx.into();
-        let vy: i64 = vy.into();
+        PhysicsConfig::default().instruction(vx, vy)
+    }
 
-        if !Self::valid(vx, vy) {
-            // use float to properly normalize here
-            let float_distance = ((vx as f64).powf(2.) + (vy as f64).powf(2.)).powf(1. / 2.);
+    /// A random instruction for the default physics, see [`PhysicsConfig::random_instruction`].
+    #[allow(dead_code)]
+    pub fn random() -> Self {
+        PhysicsConfig::default().random_instruction()
+    }
 
-            let mut vx = ((vx as f64 / float_distance) * MAX_ACCELERATION as f64) as PosType;
-            let mut vy = ((vy as f64 / float_distance) * MAX_ACCELERATION as f64) as PosType;
+    /// Load instructions from a file (see [`Instruction::from_reader`] for the format).
+    #[allow(dead_code)]
+    pub fn load(path: &PathBuf) -> Result<Vec<Instruction>, InstructionParseError> {
+        Self::from_reader(File::open(path)?)
+    }
 
-            // if we're still over, decrement both values
-            if !Self::valid(vx, vy) {
-                vx -= vx.signum();
-                vy -= vy.signum();
+    /// Read instructions in the text format, rejecting any that are longer than
+    /// [`MAX_ACCELERATION`] (instead of normalizing them, like the judge does; see
+    /// [`Instruction::from_reader_with`] for maps with other physics):
+    /// ```text
+    /// 3           // number of instructions
+    /// -16 -127    // instruction 1 vx/vy
+    /// -16 -127
+    /// -26 -125
+    /// ```
+    #[allow(dead_code)]
+    pub fn from_reader<R: Read>(reader: R) -> Result<Vec<Instruction>, InstructionParseError> {
+        Self::from_reader_with(reader, &PhysicsConfig::default())
+    }
+
+    /// Like [`Instruction::from_reader`], but for the maximum acceleration of the physics.
+    #[allow(dead_code)]
+    pub fn from_reader_with<R: Read>(
+        reader: R,
+        physics: &PhysicsConfig,
+    ) -> Result<Vec<Instruction>, InstructionParseError> {
+        let mut lines = BufReader::new(reader).lines();
+
+        let count = match lines.next() {
+            None => return Err(InstructionParseError::MissingLine(1)),
+            Some(line) => {
+                let line = line?;
+
+                match line.trim().parse::<usize>() {
+                    Ok(count) => count,
+                    Err(_) => return Err(InstructionParseError::BadInteger(1, line)),
+                }
             }
+        };
 
-            return Self { vx: vx as InstType, vy: vy as InstType };
-        }
+        let mut instructions = Vec::with_capacity(count.min(1 << 16));
 
-        assert!(Self::valid(vx, vy));
+        for (i, line) in lines.enumerate() {
+            let line_number = i + 2;
+            let line = line?;
 
-        Self {
-            vx: vx as InstType,
-            vy: vy as InstType,
-        }
-    }
+            let parts = line.split_whitespace().collect::<Vec<&str>>();
 
-    pub fn random() -> Self {
-        let mut rng = rand::rng();
+            // allow empty lines at the end of the file
+            if parts.is_empty() && instructions.len() == count {
+                continue;
+            }
 
-        Self {
-            vx: rng.random::<InstType>(),
-            vy: rng.random::<InstType>(),
-        }
-    }
+            if instructions.len() == count {
+                return Err(InstructionParseError::CountMismatch(count, line_number - 1));
+            }
 
-    pub fn load(path: &PathBuf) -> Vec<Instruction> {
-        let contents = fs::read_to_string(path).expect("Failed reading a file!");
-        let mut lines = contents.lines();
+            if parts.len() != 2 {
+                return Err(InstructionParseError::FieldCount(line_number, parts.len()));
+            }
 
-        let instruction_count = lines.next().unwrap().parse::<usize>().unwrap();
+            let mut parsed = [0; 2];
+            for (value, part) in parsed.iter_mut().zip(parts) {
+                *value = part.parse::<InstType>().map_err(|_| {
+                    InstructionParseError::BadInteger(line_number, part.to_string())
+                })?;
+            }
 
-        let mut instructions = vec![];
+            let instruction = Instruction {
+                vx: parsed[0],
+                vy: parsed[1],
+            };
 
-        for _ in 0..instruction_count {
-            let parts = lines
-                .next()
-                .expect("No more lines!")
-                .split_whitespace()
-                .collect::<Vec<&str>>();
+            if !physics.allows(instruction) {
+                return Err(InstructionParseError::TooLong(
+                    line_number,
+                    physics.max_acceleration,
+                ));
+            }
 
-            instructions.push(Instruction {
-                vx: parts[0].parse::<InstType>().unwrap(),
-                vy: parts[1].parse::<InstType>().unwrap(),
-            })
+            instructions.push(instruction);
         }
 
-        instructions
+        if instructions.len() != count {
+            return Err(InstructionParseError::CountMismatch(
+                count,
+                instructions.len(),
+            ));
+        }
+
+        Ok(instructions)
+    }
+
+    /// Save instructions to a file, in the format read by [`Instruction::load`].
+    pub fn save(path: &PathBuf, instructions: &[Instruction]) -> io::Result<()> {
+        Self::to_writer(BufWriter::new(File::create(path)?), instructions)
     }
 
-    pub fn save(path: &PathBuf, instructions: &Vec<Instruction>) {
-        let mut file = File::create(path).expect("Failed creating a file!");
+    /// Write instructions in the format read by [`Instruction::from_reader`].
+    pub fn to_writer<W: Write>(mut writer: W, instructions: &[Instruction]) -> io::Result<()> {
+        writeln!(writer, "{}", instructions.len())?;
 
         for instruction in instructions {
-            file.write_all(format!("{} {}\n", instruction.vx, instruction.vy).as_bytes())
-                .expect("Failed writing to file!");
+            writeln!(writer, "{} {}", instruction.vx, instruction.vy)?;
+        }
+
+        writer.flush()
+    }
+}
+
+/// Reasons for an instruction file failing to parse. Line numbers are 1-indexed.
+#[derive(Debug)]
+#[allow(dead_code)]
+pub enum InstructionParseError {
+    Io(std::io::Error),
+    MissingLine(usize),          // the file ended before this line
+    FieldCount(usize, usize),    // line and the number of fields on it (instead of 2)
+    BadInteger(usize, String),   // line and the field that isn't a valid count/instruction value
+    TooLong(usize, InstType),    // line of an instruction longer than the max acceleration
+    CountMismatch(usize, usize), // declared and actual number of instructions
+}
+
+impl From<std::io::Error> for InstructionParseError {
+    fn from(e: std::io::Error) -> Self {
+        InstructionParseError::Io(e)
+    }
+}
+
+impl fmt::Display for InstructionParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InstructionParseError::Io(e) => write!(f, "failed reading the instructions: {}", e),
+            InstructionParseError::MissingLine(line) => write!(f, "line {}: missing", line),
+            InstructionParseError::FieldCount(line, actual) => {
+                write!(f, "line {}: expected 2 numbers, found {}", line, actual)
+            }
+            InstructionParseError::BadInteger(line, field) => {
+                write!(f, "line {}: '{}' is not a valid number", line, field)
+            }
+            InstructionParseError::TooLong(line, max_acceleration) => write!(
+                f,
+                "line {}: instruction is longer than {}",
+                line, max_acceleration
+            ),
+            InstructionParseError::CountMismatch(declared, actual) => write!(
+                f,
+                "declared {} instructions, but found {}",
+                declared, actual
+            ),
         }
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+impl std::error::Error for InstructionParseError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct BoundingBox {
     pub min_x: SizeType,
     pub min_y: SizeType,
@@ -137,207 +390,272 @@ pub struct BoundingBox {
 }
 
 impl BoundingBox {
+    #[allow(dead_code)]
     pub fn width(&self) -> SizeType {
         self.max_x - self.min_x
     }
 
+    #[allow(dead_code)]
     pub fn height(&self) -> SizeType {
         self.max_y - self.min_y
     }
 }
 
 /// Squared Euclidean distance; useful for distance checks.
-fn distance_squared(x1: PosType, y1: PosType, x2: PosType, y2: PosType) -> PosType {
-    (x1 - x2).pow(2) + (y1 - y2).pow(2)
+///
+/// Computed in 128 bits so that it can't overflow for any pair of points; only the final
+/// sum saturates, which is fine since its square root doesn't fit into [`PosType`] anyway.
+fn distance_squared(x1: PosType, y1: PosType, x2: PosType, y2: PosType) -> u128 {
+    let dx = (x1 as i128 - x2 as i128).unsigned_abs();
+    let dy = (y1 as i128 - y2 as i128).unsigned_abs();
+
+    (dx * dx).saturating_add(dy * dy)
+}
+
+/// Exact integer square root, i.e. the largest `a` such that `a * a <= n`.
+///
+/// This is what Python's `math.isqrt` computes (see SPECIFICATION.md), so it must not go
+/// through floats, which lose precision for values above 2^53.
+pub fn isqrt(n: u128) -> u128 {
+    if n < 2 {
+        return n;
+    }
+
+    // start above the root (2^ceil(bits / 2)) and do Newton's iteration, which decreases
+    // monotonically until it reaches the floor of the root
+    let mut x = 1u128 << (128 - n.leading_zeros()).div_ceil(2);
+
+    loop {
+        let y = (x + n / x) / 2;
+
+        if y >= x {
+            return x;
+        }
+
+        x = y;
+    }
 }
 
-/// Plain-old integer Euclidean distance.
+/// Plain-old integer Euclidean distance, using the exact integer square root.
 ///
-/// Note: this implementation might break for larger position values, but since
-/// the maps are never going to be this large, I'm not fixing it now.
+/// Saturates to [`PosType::MAX`] for points that are further apart than that.
 pub fn euclidean_distance(x1: PosType, y1: PosType, x2: PosType, y2: PosType) -> PosType {
-    (distance_squared(x1, y1, x2, y2) as f64).sqrt() as PosType
+    PosType::try_from(isqrt(distance_squared(x1, y1, x2, y2))).unwrap_or(PosType::MAX)
 }
 
-#[derive(Debug, Clone)]
-pub struct Simulation {
-    pub initial_racer: Racer,
+/// Everything that describes a map, without any of the simulation state.
+///
+/// Mostly useful for storing maps in other formats than the text one (e.g. JSON, with the
+/// `serde` feature enabled).
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MapDescription {
     pub racer: Racer,
-
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub physics: PhysicsConfig,
+    pub bbox: BoundingBox,
     pub asteroids: Vec<Asteroid>,
     pub goals: Vec<Goal>,
-    pub bbox: BoundingBox,
+}
 
-    pub reached_goals: Vec<bool>,
+impl From<MapDescription> for Map {
+    fn from(map: MapDescription) -> Self {
+        Map::with_physics(map.racer, map.asteroids, map.goals, map.bbox, map.physics)
+    }
+}
 
-    _grid: HashMap<(PosType, PosType), Vec<Asteroid>>,
-    _cell_size: PosType,
+impl From<MapDescription> for Simulation {
+    fn from(map: MapDescription) -> Self {
+        Simulation::from(Map::from(map))
+    }
 }
 
+/// The part of the simulation that changes between ticks.
 ///
-/// # Examples
-/// ```
-/// let map_path = PathBuf::from("../../maps/test.txt");
-///
-/// let mut simulation = Simulation::load(&map_path);
-///
-/// let mut tick_result: TickResult = 0;
-///
-/// println!("Running simulation until collision...");
-///
-/// while tick_result & TickFlag::COLLIDED == 0 {
-///     tick_result = simulation.tick(Instruction::new(0, MAX_ACCELERATION));
-///
-///     println!("{:?}", simulation.racer);
-/// }
-///
-/// println!("Bam!");
-/// ```
+/// Snapshotting and restoring it is O(goals), so it's what search algorithms should store
+/// instead of cloning the whole [`Simulation`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SimState {
+    pub racer: Racer,
+    pub reached_goals: Vec<bool>,
+}
+
+/// The final state of a run, along with the results of each of its ticks.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RunResult {
+    pub state: SimState,
+    pub tick_results: Vec<TickResult>,
+}
+
+/// The static part of the simulation: the world and its spatial index.
 ///
-impl Simulation {
-    pub fn new(racer: Racer, asteroids: Vec<Asteroid>, goals: Vec<Goal>, bbox: BoundingBox) -> Self {
-        let reached_goals = vec![false; goals.len()];
+/// It never changes once built, so any number of [`Runner`]s (or [`Simulation`]s, via [`Arc`])
+/// can share a single map.
+#[derive(Debug, Clone)]
+pub struct Map {
+    pub racer: Racer,
+    pub physics: PhysicsConfig,
+
+    pub asteroids: Vec<Asteroid>,
+    pub goals: Vec<Goal>,
+    pub bbox: BoundingBox,
+
+    _asteroid_grid: Grid,
+    _goal_grid: Grid,
+}
+
+impl Map {
+    pub fn new(
+        racer: Racer,
+        asteroids: Vec<Asteroid>,
+        goals: Vec<Goal>,
+        bbox: BoundingBox,
+    ) -> Self {
+        Self::with_physics(racer, asteroids, goals, bbox, PhysicsConfig::default())
+    }
 
-        let mut simulation = Self {
-            initial_racer: racer,
+    pub fn with_physics(
+        racer: Racer,
+        asteroids: Vec<Asteroid>,
+        goals: Vec<Goal>,
+        bbox: BoundingBox,
+        physics: PhysicsConfig,
+    ) -> Self {
+        Self {
             racer,
+            physics,
+            _asteroid_grid: Grid::new(&asteroids, physics.cell_size),
+            _goal_grid: Grid::new(&goals, physics.cell_size),
             asteroids,
             goals,
             bbox,
-            reached_goals,
-            _grid: HashMap::new(),
-            _cell_size: CELL_SIZE,
-        };
-
-        for &asteroid in &simulation.asteroids {
-            let (min_x, min_y) = simulation.coordinate_to_grid(
-                asteroid.x - asteroid.radius - racer.radius,
-                asteroid.y - asteroid.radius - racer.radius,
-            );
-
-            let (max_x, max_y) = simulation.coordinate_to_grid(
-                asteroid.x + asteroid.radius + racer.radius,
-                asteroid.y + asteroid.radius + racer.radius,
-            );
-
-            for grid_x in min_x..=max_x {
-                for grid_y in min_y..=max_y {
-                    simulation
-                        ._grid
-                        .entry((grid_x, grid_y))
-                        .or_insert(vec![])
-                        .push(asteroid);
-                }
-            }
         }
-
-        simulation
     }
 
-    fn coordinate_to_grid(&self, x: PosType, y: PosType) -> (PosType, PosType) {
-        (x / self._cell_size, y / self._cell_size)
+    /// The state of a racer at the start of the map, with no goals reached.
+    pub fn initial_state(&self) -> SimState {
+        SimState {
+            racer: Racer {
+                vx: 0,
+                vy: 0,
+                ..self.racer
+            },
+            reached_goals: vec![false; self.goals.len()],
+        }
     }
 
-    fn move_racer(&mut self, instruction: Instruction) {
-        self.racer.vx = (self.racer.vx * DRAG_FRACTION.0) / DRAG_FRACTION.1;
-        self.racer.vy = (self.racer.vy * DRAG_FRACTION.0) / DRAG_FRACTION.1;
+    /// The first asteroid (by index) containing the point, if any.
+    pub fn asteroid_at(&self, x: PosType, y: PosType) -> Option<usize> {
+        (self._asteroid_grid.query(x, y, 0).iter().copied()).find(|&i| {
+            let asteroid = &self.asteroids[i];
+            euclidean_distance(x, y, asteroid.x, asteroid.y) <= asteroid.radius
+        })
+    }
 
-        self.racer.vx += instruction.vx as SpeedType;
-        self.racer.vy += instruction.vy as SpeedType;
+    fn move_racer(&self, racer: &mut Racer, instruction: Instruction) {
+        let (numerator, denominator) = self.physics.drag_fraction;
 
-        self.racer.x += self.racer.vx as PosType;
-        self.racer.y += self.racer.vy as PosType;
-    }
+        racer.vx = (racer.vx * numerator) / denominator;
+        racer.vy = (racer.vy * numerator) / denominator;
 
-    fn push_from_asteroids(&mut self) -> bool {
-        let grid_coordinate = self.coordinate_to_grid(self.racer.x, self.racer.y);
+        racer.vx += instruction.vx as SpeedType;
+        racer.vy += instruction.vy as SpeedType;
 
-        match self._grid.get(&grid_coordinate) {
-            None => false,
-            Some(asteroids) => {
-                for asteroid in asteroids {
-                    // not colliding, nothing to be done
-                    if euclidean_distance(self.racer.x, self.racer.y, asteroid.x, asteroid.y)
-                        > self.racer.radius + asteroid.radius
-                    {
-                        continue;
-                    }
+        racer.x += racer.vx as PosType;
+        racer.y += racer.vy as PosType;
+    }
 
-                    // the vector to push the racer out by
-                    let nx = self.racer.x - asteroid.x;
-                    let ny = self.racer.y - asteroid.y;
+    fn push_from_asteroids(&self, racer: &mut Racer) -> bool {
+        // the grid returns the asteroids in the order they were added, which is important,
+        // since we only push out of the first one we collide with
+        for &i in self
+            ._asteroid_grid
+            .query(racer.x, racer.y, racer.radius)
+            .iter()
+        {
+            let asteroid = &self.asteroids[i];
+
+            // not colliding, nothing to be done
+            if euclidean_distance(racer.x, racer.y, asteroid.x, asteroid.y)
+                > racer.radius + asteroid.radius
+            {
+                continue;
+            }
 
-                    // how much to push by
-                    let distance =
-                        euclidean_distance(self.racer.x, self.racer.y, asteroid.x, asteroid.y);
-                    let push_by = distance - (self.racer.radius + asteroid.radius);
+            // the vector to push the racer out by, in 128 bits like [`distance_squared`],
+            // since its product with the push can't fit into [`PosType`] on big maps
+            let nx = racer.x as i128 - asteroid.x as i128;
+            let ny = racer.y as i128 - asteroid.y as i128;
 
-                    // the actual push
-                    self.racer.x -= (nx * push_by) / distance;
-                    self.racer.y -= (ny * push_by) / distance;
+            // how much to push by
+            let distance = euclidean_distance(racer.x, racer.y, asteroid.x, asteroid.y) as i128;
+            let push_by = distance - (racer.radius as i128 + asteroid.radius as i128);
 
-                    return true;
-                }
+            // the actual push
+            racer.x = (racer.x as i128 - (nx * push_by) / distance) as PosType;
+            racer.y = (racer.y as i128 - (ny * push_by) / distance) as PosType;
 
-                false
-            }
+            return true;
         }
+
+        false
     }
 
-    fn push_from_bounding_box(&mut self) -> bool {
+    fn push_from_bounding_box(&self, racer: &mut Racer) -> bool {
         // not pretty but easy to read :)
         let mut collided = false;
 
-        if self.racer.x - self.racer.radius < self.bbox.min_x {
-            self.racer.x = self.bbox.min_x + self.racer.radius;
+        if racer.x - racer.radius < self.bbox.min_x {
+            racer.x = self.bbox.min_x + racer.radius;
             collided = true;
         }
-        if self.racer.x + self.racer.radius > self.bbox.max_x {
-            self.racer.x = self.bbox.max_x - self.racer.radius;
+        if racer.x + racer.radius > self.bbox.max_x {
+            racer.x = self.bbox.max_x - racer.radius;
             collided = true;
         }
-        if self.racer.y - self.racer.radius < self.bbox.min_y {
-            self.racer.y = self.bbox.min_y + self.racer.radius;
+        if racer.y - racer.radius < self.bbox.min_y {
+            racer.y = self.bbox.min_y + racer.radius;
             collided = true;
         }
-        if self.racer.y + self.racer.radius > self.bbox.max_y {
-            self.racer.y = self.bbox.max_y - self.racer.radius;
+        if racer.y + racer.radius > self.bbox.max_y {
+            racer.y = self.bbox.max_y - racer.radius;
             collided = true;
         }
 
         collided
     }
 
-    fn check_goal(&mut self) -> bool {
+    fn check_goal(&self, racer: &Racer, reached_goals: &mut [bool]) -> bool {
         let mut new_goal_reached = false;
 
-        for (i, goal) in self.goals.iter().enumerate() {
-            if euclidean_distance(self.racer.x, self.racer.y, goal.x, goal.y)
-                <= (self.racer.radius + goal.radius)
-            {
-                if !&self.reached_goals[i] {
+        for &i in self._goal_grid.query(racer.x, racer.y, racer.radius).iter() {
+            let goal = &self.goals[i];
+
+            // only intersecting counts, merely touching the goal is not enough
+            if euclidean_distance(racer.x, racer.y, goal.x, goal.y) < (racer.radius + goal.radius) {
+                if !reached_goals[i] {
                     new_goal_reached = true;
                 }
 
-                self.reached_goals[i] = true;
+                reached_goals[i] = true;
             }
         }
 
         new_goal_reached
     }
 
-    fn resolve_collisions(&mut self) -> bool {
+    fn resolve_collisions(&self, racer: &mut Racer) -> bool {
         let mut collided = false;
 
-        for _ in 0..MAX_COLLISION_RESOLUTIONS {
+        for _ in 0..self.physics.max_collision_resolutions {
             let mut collided_this_iteration = false;
 
-            if self.push_from_asteroids() {
+            if self.push_from_asteroids(racer) {
                 collided_this_iteration = true;
                 collided = true;
             }
 
-            if self.push_from_bounding_box() {
+            if self.push_from_bounding_box(racer) {
                 collided_this_iteration = true;
                 collided = true;
             }
@@ -348,30 +666,24 @@ impl Simulation {
         }
 
         if collided {
-            self.racer.vx = (self.racer.vx * COLLISION_FRACTION.0) / COLLISION_FRACTION.1;
-            self.racer.vy = (self.racer.vy * COLLISION_FRACTION.0) / COLLISION_FRACTION.1;
+            let (numerator, denominator) = self.physics.collision_fraction;
+
+            racer.vx = (racer.vx * numerator) / denominator;
+            racer.vy = (racer.vy * numerator) / denominator;
         }
 
         collided
     }
 
-    pub fn finished(&self) -> bool {
-        self.reached_goals.iter().all(|v| *v)
-    }
-
-    pub fn restart(&mut self) {
-        self.racer.x = self.initial_racer.x;
-        self.racer.y = self.initial_racer.y;
-        self.racer.vx = 0;
-        self.racer.vy = 0;
-
-        self.reached_goals.fill(false);
-    }
-
-    pub fn tick(&mut self, instruction: Instruction) -> TickResult {
-        self.move_racer(instruction);
-        let collided = self.resolve_collisions();
-        let goal = self.check_goal();
+    fn tick_racer(
+        &self,
+        racer: &mut Racer,
+        reached_goals: &mut [bool],
+        instruction: Instruction,
+    ) -> TickResult {
+        self.move_racer(racer, instruction);
+        let collided = self.resolve_collisions(racer);
+        let goal = self.check_goal(racer, reached_goals);
 
         let mut result: TickResult = 0;
 
@@ -386,75 +698,1213 @@ impl Simulation {
         result
     }
 
-    pub fn simulate(&mut self, instructions: &Vec<Instruction>) -> Vec<TickResult> {
-        self.restart();
+    /// Simulate a single tick of the given state on this map.
+    pub fn tick(&self, state: &mut SimState, instruction: Instruction) -> TickResult {
+        self.tick_racer(&mut state.racer, &mut state.reached_goals, instruction)
+    }
 
-        let mut results = vec![];
+    /// Simulate the instructions, starting from the given state.
+    pub fn simulate_from(&self, mut state: SimState, instructions: &[Instruction]) -> RunResult {
+        let tick_results = instructions
+            .iter()
+            .map(|&instruction| self.tick(&mut state, instruction))
+            .collect();
 
-        for instruction in instructions {
-            results.push(self.tick(*instruction));
+        RunResult {
+            state,
+            tick_results,
         }
+    }
+
+    /// Simulate each of the instruction sequences from the start of the map.
+    ///
+    /// The runs are evaluated in parallel if the `rayon` feature is enabled; the results are
+    /// the same (and in the same order) either way.
+    #[allow(dead_code)]
+    pub fn simulate_batch<S>(&self, runs: &[S]) -> Vec<RunResult>
+    where
+        S: AsRef<[Instruction]> + Sync,
+    {
+        #[cfg(feature = "rayon")]
+        let runs = runs.par_iter();
+        #[cfg(not(feature = "rayon"))]
+        let runs = runs.iter();
 
-        results
+        runs.map(|instructions| self.simulate_from(self.initial_state(), instructions.as_ref()))
+            .collect()
     }
 
-    pub fn load(path: &PathBuf) -> Self {
-        let binding = fs::read_to_string(path).unwrap();
-        let mut lines = binding.lines();
+    /// Like [`Map::simulate_batch`], but each run continues from its own state.
+    pub fn simulate_batch_from<S>(&self, runs: &[(SimState, S)]) -> Vec<RunResult>
+    where
+        S: AsRef<[Instruction]> + Sync,
+    {
+        #[cfg(feature = "rayon")]
+        let runs = runs.par_iter();
+        #[cfg(not(feature = "rayon"))]
+        let runs = runs.iter();
 
-        let mut parts_fn = || {
-            lines
-                .next()
-                .unwrap()
-                .split_whitespace()
-                .collect::<Vec<&str>>()
-        };
+        runs.map(|(state, instructions)| self.simulate_from(state.clone(), instructions.as_ref()))
+            .collect()
+    }
+
+    /// Load the map from a file (see [`Map::from_reader`] for the format).
+    pub fn load(path: &PathBuf) -> Result<Self, MapParseError> {
+        Self::from_reader(File::open(path)?)
+    }
+
+    /// Save the map to a file (see [`Map::from_reader`] for the format).
+    #[allow(dead_code)]
+    pub fn save(&self, path: &PathBuf) -> io::Result<()> {
+        self.to_writer(BufWriter::new(File::create(path)?))
+    }
+
+    /// Write the map in the text format read by [`Map::from_reader`].
+    pub fn to_writer<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        if let Some(header) = self.physics.to_header() {
+            writeln!(writer, "{}", header)?;
+        }
+
+        let racer = self.racer;
+        writeln!(writer, "{} {} {}", racer.x, racer.y, racer.radius)?;
+
+        let bbox = self.bbox;
+        writeln!(
+            writer,
+            "{} {} {} {}",
+            bbox.min_x, bbox.min_y, bbox.max_x, bbox.max_y
+        )?;
+
+        for objects in [&self.asteroids, &self.goals] {
+            writeln!(writer, "{}", objects.len())?;
+
+            for object in objects {
+                writeln!(writer, "{} {} {}", object.x, object.y, object.radius)?;
+            }
+        }
+
+        writer.flush()
+    }
+
+    /// The map as plain data, e.g. for serializing it.
+    #[allow(dead_code)]
+    pub fn description(&self) -> MapDescription {
+        MapDescription {
+            racer: self.racer,
+            physics: self.physics,
+            bbox: self.bbox,
+            asteroids: self.asteroids.clone(),
+            goals: self.goals.clone(),
+        }
+    }
+
+    /// Read the map in the text format:
+    /// ```text
+    /// physics drag=19/20   // optional, overrides the default physics (see PhysicsConfig)
+    /// 0 0 5                // racer x/y/radius
+    /// -100 -100 100 100    // bounding box (min_x/min_y/max_x/max_y)
+    /// 2                    // number of asteroids
+    /// 10 -10 10            // asteroid 1 x/y/radius
+    /// 20 20 50             // asteroid 2 x/y/radius
+    /// 1                    // number of goals
+    /// 100 100 10           // goal 1 x/y/radius
+    /// ```
+    pub fn from_reader<R: Read>(mut reader: R) -> Result<Self, MapParseError> {
+        let mut contents = String::new();
+        reader.read_to_string(&mut contents)?;
+
+        contents.parse()
+    }
+}
+
+impl FromStr for Map {
+    type Err = MapParseError;
 
-        let racer_parts = parts_fn();
+    fn from_str(contents: &str) -> Result<Self, Self::Err> {
+        let mut lines = MapLines::new(contents);
 
+        let physics = lines.next_physics()?;
+
+        let (_, [x, y, radius]) = lines.next_fields()?;
         let racer = Racer {
-            x: racer_parts[0].parse::<PosType>().unwrap(),
-            y: racer_parts[1].parse::<PosType>().unwrap(),
-            radius: racer_parts[2].parse::<SizeType>().unwrap(),
+            x,
+            y,
+            radius,
             vx: 0,
             vy: 0,
         };
 
-        let bb_parts = parts_fn();
-
+        let (_, [min_x, min_y, max_x, max_y]) = lines.next_fields()?;
         let bbox = BoundingBox {
-            min_x: bb_parts[0].parse::<SizeType>().unwrap(),
-            min_y: bb_parts[1].parse::<SizeType>().unwrap(),
-            max_x: bb_parts[2].parse::<SizeType>().unwrap(),
-            max_y: bb_parts[3].parse::<SizeType>().unwrap(),
+            min_x,
+            min_y,
+            max_x,
+            max_y,
         };
 
-        let asteroid_count = parts_fn()[0].parse::<usize>().unwrap();
+        let asteroids = lines.next_objects()?;
+        let goals = lines.next_objects()?;
 
-        let mut asteroids = vec![];
-        for _ in 0..asteroid_count {
-            let asteroid_parts = parts_fn();
+        lines.expect_end()?;
 
-            asteroids.push(Asteroid {
-                x: asteroid_parts[0].parse::<PosType>().unwrap(),
-                y: asteroid_parts[1].parse::<PosType>().unwrap(),
-                radius: asteroid_parts[2].parse::<PosType>().unwrap(),
-            });
-        }
+        Ok(Self::with_physics(racer, asteroids, goals, bbox, physics))
+    }
+}
 
-        let goal_count = parts_fn()[0].parse::<usize>().unwrap();
+/// A lightweight run of a racer on a borrowed [`Map`].
+///
+/// # Examples
+/// ```
+/// let map = Map::load(&PathBuf::from("../../maps/test.txt")).unwrap();
+///
+/// // any number of runners can use the same map
+/// let mut left = Runner::new(&map);
+/// let mut right = Runner::new(&map);
+///
+/// left.tick(Instruction::new(-MAX_ACCELERATION, 0));
+/// right.tick(Instruction::new(MAX_ACCELERATION, 0));
+/// ```
+#[derive(Debug, Clone)]
+#[allow(dead_code)]
+pub struct Runner<'a> {
+    pub map: &'a Map,
+    pub state: SimState,
+}
 
-        let mut goals = vec![];
-        for _ in 0..goal_count {
-            let goal_parts = parts_fn();
+#[allow(dead_code)]
+impl<'a> Runner<'a> {
+    pub fn new(map: &'a Map) -> Self {
+        Self {
+            map,
+            state: map.initial_state(),
+        }
+    }
 
-            goals.push(Asteroid {
-                x: goal_parts[0].parse::<PosType>().unwrap(),
-                y: goal_parts[1].parse::<PosType>().unwrap(),
-                radius: goal_parts[2].parse::<PosType>().unwrap(),
-            });
+    fn run(&mut self) -> Run<'_> {
+        Run {
+            map: self.map,
+            racer: &mut self.state.racer,
+            reached_goals: &mut self.state.reached_goals,
         }
+    }
+
+    pub fn finished(&self) -> bool {
+        all_reached(&self.state.reached_goals)
+    }
+
+    pub fn restart(&mut self) {
+        self.run().restart()
+    }
+
+    pub fn tick(&mut self, instruction: Instruction) -> TickResult {
+        self.run().tick(instruction)
+    }
+
+    /// Like [`Simulation::simulate`].
+    pub fn simulate(&mut self, instructions: &[Instruction]) -> Vec<TickResult> {
+        self.run().simulate(instructions)
+    }
+
+    /// Like [`Simulation::simulate_until_finished`].
+    pub fn simulate_until_finished(&mut self, instructions: &[Instruction]) -> Option<usize> {
+        self.run().simulate_until_finished(instructions)
+    }
+}
+
+fn all_reached(reached_goals: &[bool]) -> bool {
+    reached_goals.iter().all(|v| *v)
+}
+
+/// A racer on a map, borrowed from a [`Runner`] or a [`Simulation`] so that both run
+/// instructions the same way.
+struct Run<'a> {
+    map: &'a Map,
+    racer: &'a mut Racer,
+    reached_goals: &'a mut Vec<bool>,
+}
+
+impl Run<'_> {
+    fn restart(&mut self) {
+        let initial = self.map.initial_state();
+
+        *self.racer = initial.racer;
+        self.reached_goals.clone_from(&initial.reached_goals);
+    }
+
+    fn tick(&mut self, instruction: Instruction) -> TickResult {
+        self.map
+            .tick_racer(self.racer, self.reached_goals, instruction)
+    }
+
+    #[allow(dead_code)]
+    fn simulate(&mut self, instructions: &[Instruction]) -> Vec<TickResult> {
+        self.restart();
+
+        instructions
+            .iter()
+            .map(|&instruction| self.tick(instruction))
+            .collect()
+    }
+
+    #[allow(dead_code)]
+    fn simulate_until_finished(&mut self, instructions: &[Instruction]) -> Option<usize> {
+        self.restart();
+
+        if all_reached(self.reached_goals) {
+            return Some(0);
+        }
+
+        for (i, &instruction) in instructions.iter().enumerate() {
+            self.tick(instruction);
+
+            if all_reached(self.reached_goals) {
+                return Some(i + 1);
+            }
+        }
+
+        None
+    }
+}
+
+/// The simulation of a single racer on a map.
+///
+/// The [`Map`] is shared via [`Arc`], so cloning a simulation only copies its state.
+#[derive(Debug, Clone)]
+pub struct Simulation {
+    pub map: Arc<Map>,
+
+    pub racer: Racer,
+    pub reached_goals: Vec<bool>,
+
+    // a list of simulation states that can be popped (restored to)
+    _pushed_states: Vec<SimState>,
+}
+
+impl From<Map> for Simulation {
+    fn from(map: Map) -> Self {
+        Simulation::from(Arc::new(map))
+    }
+}
+
+impl From<Arc<Map>> for Simulation {
+    fn from(map: Arc<Map>) -> Self {
+        let SimState {
+            racer,
+            reached_goals,
+        } = map.initial_state();
+
+        Self {
+            map,
+            racer,
+            reached_goals,
+            _pushed_states: vec![],
+        }
+    }
+}
+
+///
+/// # Examples
+/// ```
+/// let map_path = PathBuf::from("../../maps/test.txt");
+///
+/// let mut simulation = Simulation::load(&map_path).unwrap();
+///
+/// let mut tick_result: TickResult = 0;
+///
+/// println!("Running simulation until collision...");
+///
+/// while tick_result & TickFlag::COLLIDED == 0 {
+///     tick_result = simulation.tick(Instruction::new(0, MAX_ACCELERATION));
+///
+///     println!("{:?}", simulation.racer);
+/// }
+///
+/// println!("Bam!");
+/// ```
+///
+impl Simulation {
+    #[allow(dead_code)]
+    pub fn new(
+        racer: Racer,
+        asteroids: Vec<Asteroid>,
+        goals: Vec<Goal>,
+        bbox: BoundingBox,
+    ) -> Self {
+        Self::from(Map::new(racer, asteroids, goals, bbox))
+    }
+
+    fn run(&mut self) -> Run<'_> {
+        Run {
+            map: &self.map,
+            racer: &mut self.racer,
+            reached_goals: &mut self.reached_goals,
+        }
+    }
+
+    pub fn finished(&self) -> bool {
+        all_reached(&self.reached_goals)
+    }
+
+    pub fn restart(&mut self) {
+        self.run().restart()
+    }
+
+    /// The current state of the simulation.
+    #[allow(dead_code)]
+    pub fn state(&self) -> SimState {
+        SimState {
+            racer: self.racer,
+            reached_goals: self.reached_goals.clone(),
+        }
+    }
+
+    /// Restore a state previously obtained from [`Simulation::state`].
+    #[allow(dead_code)]
+    pub fn restore(&mut self, state: &SimState) {
+        self.racer = state.racer;
+        self.reached_goals.clone_from(&state.reached_goals);
+    }
+
+    /// Push (save) the current state of the simulation. Can be popped (restored) later.
+    #[allow(dead_code)]
+    pub fn push(&mut self) {
+        self._pushed_states.push(self.state());
+    }
+
+    /// Pop (restore) the previously pushed state.
+    #[allow(dead_code)]
+    pub fn pop(&mut self) {
+        let state = self._pushed_states.pop().expect("No states to pop!");
+        self.restore(&state);
+    }
+
+    /// Apply the previously pushed state without popping it.
+    #[allow(dead_code)]
+    pub fn apply(&mut self) {
+        let state = self._pushed_states.pop().expect("No states to apply!");
+        self.restore(&state);
+        self._pushed_states.push(state);
+    }
+
+    pub fn tick(&mut self, instruction: Instruction) -> TickResult {
+        self.run().tick(instruction)
+    }
+
+    /// Simulate the instructions from the start, returning the result of each tick.
+    #[allow(dead_code)]
+    pub fn simulate(&mut self, instructions: &[Instruction]) -> Vec<TickResult> {
+        self.run().simulate(instructions)
+    }
+
+    /// Simulate the instructions from the start, stopping once all goals are reached.
+    ///
+    /// Returns the number of ticks it took to finish (the instructions after that are not
+    /// simulated), or `None` if the racer didn't finish.
+    #[allow(dead_code)]
+    pub fn simulate_until_finished(&mut self, instructions: &[Instruction]) -> Option<usize> {
+        self.run().simulate_until_finished(instructions)
+    }
+
+    /// Load the simulation from a map file (see [`Map::from_reader`] for the format).
+    pub fn load(path: &PathBuf) -> Result<Self, MapParseError> {
+        Map::load(path).map(Self::from)
+    }
+
+    /// Save the simulation's map to a file (see [`Map::from_reader`] for the format).
+    #[allow(dead_code)]
+    pub fn save(&self, path: &PathBuf) -> io::Result<()> {
+        self.map.save(path)
+    }
+
+    /// Write the simulation's map in the text format read by [`Map::from_reader`].
+    ///
+    /// The racer is written in its initial position, so the map is the same as the one loaded.
+    #[allow(dead_code)]
+    pub fn to_writer<W: Write>(&self, writer: W) -> io::Result<()> {
+        self.map.to_writer(writer)
+    }
+
+    /// The map of this simulation, with the racer in its initial position.
+    #[allow(dead_code)]
+    pub fn description(&self) -> MapDescription {
+        self.map.description()
+    }
+
+    /// Read the simulation from a map (see [`Map::from_reader`] for the format).
+    pub fn from_reader<R: Read>(reader: R) -> Result<Self, MapParseError> {
+        Map::from_reader(reader).map(Self::from)
+    }
+}
+
+impl FromStr for Simulation {
+    type Err = MapParseError;
+
+    fn from_str(contents: &str) -> Result<Self, Self::Err> {
+        contents.parse::<Map>().map(Self::from)
+    }
+}
+
+/// Reasons for a map failing to parse. Line numbers are 1-indexed.
+#[derive(Debug)]
+pub enum MapParseError {
+    Io(std::io::Error),
+    MissingLine(usize),                 // the file ended before this line
+    FieldCount(usize, usize, usize),    // line, expected and actual number of fields
+    BadInteger(usize, String),          // line and the field that isn't a valid integer
+    CountMismatch(usize, usize, usize), // line of the count, declared and actual number of objects
+    TrailingGarbage(usize),             // non-empty line after the last goal
+    BadPhysics(usize, String),          // line and the invalid setting of the physics header
+}
+
+impl From<std::io::Error> for MapParseError {
+    fn from(e: std::io::Error) -> Self {
+        MapParseError::Io(e)
+    }
+}
+
+impl fmt::Display for MapParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MapParseError::Io(e) => write!(f, "failed reading the map: {}", e),
+            MapParseError::MissingLine(line) => write!(f, "line {}: missing", line),
+            MapParseError::FieldCount(line, expected, actual) => {
+                write!(
+                    f,
+                    "line {}: expected {} numbers, found {}",
+                    line, expected, actual
+                )
+            }
+            MapParseError::BadInteger(line, field) => {
+                write!(f, "line {}: '{}' is not a valid integer", line, field)
+            }
+            MapParseError::CountMismatch(line, declared, actual) => write!(
+                f,
+                "line {}: declared {} objects, but only {} follow",
+                line, declared, actual
+            ),
+            MapParseError::TrailingGarbage(line) => {
+                write!(f, "line {}: unexpected content after the last goal", line)
+            }
+            MapParseError::BadPhysics(line, setting) => {
+                write!(f, "line {}: invalid physics setting '{}'", line, setting)
+            }
+        }
+    }
+}
+
+impl std::error::Error for MapParseError {}
+
+/// Line-by-line reader of the map format, keeping track of line numbers for errors.
+struct MapLines<'a> {
+    lines: std::iter::Peekable<std::iter::Enumerate<std::str::Lines<'a>>>,
+    line_number: usize,
+}
+
+impl<'a> MapLines<'a> {
+    fn new(contents: &'a str) -> Self {
+        Self {
+            lines: contents.lines().enumerate().peekable(),
+            line_number: 0,
+        }
+    }
+
+    fn next_line(&mut self) -> Option<(usize, &'a str)> {
+        let (i, line) = self.lines.next()?;
+        self.line_number = i + 1;
+
+        Some((self.line_number, line))
+    }
+
+    /// Parse the physics header, if there is one.
+    fn next_physics(&mut self) -> Result<PhysicsConfig, MapParseError> {
+        match self.lines.peek() {
+            Some((_, line)) if line.starts_with("physics") => {
+                let (line_number, line) = self.next_line().unwrap();
+
+                PhysicsConfig::from_header(line)
+                    .map_err(|setting| MapParseError::BadPhysics(line_number, setting))
+            }
+            _ => Ok(PhysicsConfig::default()),
+        }
+    }
+
+    /// Parse the next line, which must contain exactly `N` integers.
+    fn next_fields<T: FromStr, const N: usize>(
+        &mut self,
+    ) -> Result<(usize, [T; N]), MapParseError> {
+        let (line_number, line) = self
+            .next_line()
+            .ok_or(MapParseError::MissingLine(self.line_number + 1))?;
+
+        let parts = line.split_whitespace().collect::<Vec<&str>>();
+
+        if parts.len() != N {
+            return Err(MapParseError::FieldCount(line_number, N, parts.len()));
+        }
+
+        let mut parsed = Vec::with_capacity(N);
+        for part in parts {
+            parsed.push(
+                part.parse::<T>()
+                    .map_err(|_| MapParseError::BadInteger(line_number, part.to_string()))?,
+            );
+        }
+
+        match parsed.try_into() {
+            Ok(fields) => Ok((line_number, fields)),
+            Err(_) => unreachable!(),
+        }
+    }
+
+    /// Parse a count line followed by that many `x y radius` lines.
+    fn next_objects(&mut self) -> Result<Vec<Asteroid>, MapParseError> {
+        let (count_line, [count]) = self.next_fields::<usize, 1>()?;
+
+        let mut objects = vec![];
+        for _ in 0..count {
+            let (_, [x, y, radius]) = match self.next_fields() {
+                Err(MapParseError::MissingLine(_)) => {
+                    return Err(MapParseError::CountMismatch(
+                        count_line,
+                        count,
+                        objects.len(),
+                    ))
+                }
+                result => result?,
+            };
+
+            objects.push(Asteroid { x, y, radius });
+        }
+
+        Ok(objects)
+    }
+
+    /// Only empty lines may follow the last goal.
+    fn expect_end(&mut self) -> Result<(), MapParseError> {
+        while let Some((line_number, line)) = self.next_line() {
+            if !line.trim().is_empty() {
+                return Err(MapParseError::TrailingGarbage(line_number));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use std::fs;
+
+    /// Slow but obviously correct integer square root to compare against.
+    fn reference_isqrt(n: u128) -> u128 {
+        let (mut low, mut high) = (0u128, u64::MAX as u128 + 1);
+
+        // invariant: low * low <= n < high * high
+        while high - low > 1 {
+            let mid = low + (high - low) / 2;
+
+            match mid.checked_mul(mid) {
+                Some(square) if square <= n => low = mid,
+                _ => high = mid,
+            }
+        }
+
+        low
+    }
+
+    #[test]
+    fn test_isqrt_edge_cases() {
+        let mut values = vec![0, 1, 2, 3, 4, u128::MAX, u128::MAX - 1];
+
+        for i in 0..128 {
+            let power = 1u128 << i;
+            values.extend([power - 1, power, power + 1]);
+        }
+
+        for root in [
+            2u128,
+            3,
+            10,
+            1 << 26,
+            (1 << 26) + 1,
+            94906265,
+            u64::MAX as u128,
+        ] {
+            let square = root * root;
+            values.extend([square - 1, square, square + 1]);
+        }
+
+        // values where f64 rounding makes `(n as f64).sqrt() as u128` wrong
+        values.extend([
+            (1u128 << 106) - 1,
+            4503599761588224,
+            999999999999999999999999,
+        ]);
+
+        for n in values {
+            assert_eq!(isqrt(n), reference_isqrt(n), "isqrt({}) is wrong", n);
+        }
+    }
+
+    #[test]
+    fn test_isqrt_random() {
+        let mut rng = StdRng::seed_from_u64(0);
+
+        for _ in 0..100_000 {
+            // pick the magnitude uniformly, otherwise almost all values would be huge
+            let n = rng.random::<u128>() >> rng.random_range(0..128);
+            let root = isqrt(n);
+
+            assert_eq!(root, reference_isqrt(n), "isqrt({}) is wrong", n);
+            assert!(root * root <= n);
+            assert!((root + 1)
+                .checked_mul(root + 1)
+                .is_none_or(|square| square > n));
+        }
+    }
+
+    #[test]
+    fn test_euclidean_distance_random() {
+        let mut rng = StdRng::seed_from_u64(0);
+
+        for _ in 0..100_000 {
+            let shift = rng.random_range(0..64);
+            let mut coordinate = || rng.random::<PosType>() >> shift;
+
+            let (x1, y1, x2, y2) = (coordinate(), coordinate(), coordinate(), coordinate());
+            let distance = euclidean_distance(x1, y1, x2, y2);
+
+            assert_eq!(distance, euclidean_distance(x2, y2, x1, y1));
+
+            let expected = reference_isqrt(distance_squared(x1, y1, x2, y2));
+            assert_eq!(distance as u128, expected.min(PosType::MAX as u128));
+        }
+    }
+
+    #[test]
+    fn test_instructions_round_trip() {
+        let instructions = (0..1000).map(|_| Instruction::random()).collect::<Vec<_>>();
+
+        let mut buffer = vec![];
+        Instruction::to_writer(&mut buffer, &instructions).unwrap();
+
+        assert_eq!(
+            Instruction::from_reader(buffer.as_slice()).unwrap(),
+            instructions
+        );
+
+        let path = std::env::temp_dir().join("rusteracer-round-trip.txt");
+        Instruction::save(&path, &instructions).unwrap();
+        assert_eq!(Instruction::load(&path).unwrap(), instructions);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_instruction_parse_errors() {
+        fn error(instructions: &str) -> InstructionParseError {
+            Instruction::from_reader(instructions.as_bytes()).unwrap_err()
+        }
+
+        assert!(Instruction::from_reader("2\n-127 0\n89 -90\n\n".as_bytes()).is_ok());
+
+        assert!(matches!(error(""), InstructionParseError::MissingLine(1)));
+        assert!(matches!(
+            error("a\n"),
+            InstructionParseError::BadInteger(1, _)
+        ));
+        assert!(matches!(
+            error("1\n1\n"),
+            InstructionParseError::FieldCount(2, 1)
+        ));
+        assert!(matches!(
+            error("1\n1 2 3\n"),
+            InstructionParseError::FieldCount(2, 3)
+        ));
+        assert!(matches!(
+            error("2\n1 2\n1 200\n"),
+            InstructionParseError::BadInteger(3, _)
+        ));
+        assert!(matches!(
+            error("1\n-128 0\n"),
+            InstructionParseError::TooLong(2, 127)
+        ));
+        assert!(matches!(
+            error("1\n90 90\n"),
+            InstructionParseError::TooLong(2, 127)
+        ));
+        assert!(matches!(
+            error("3\n1 2\n1 2\n"),
+            InstructionParseError::CountMismatch(3, 2)
+        ));
+        assert!(matches!(
+            error("1\n1 2\n1 2\n"),
+            InstructionParseError::CountMismatch(1, 2)
+        ));
+    }
+
+    #[test]
+    fn test_instructions_honor_physics() {
+        let physics = PhysicsConfig {
+            max_acceleration: 50,
+            ..PhysicsConfig::default()
+        };
+
+        for _ in 0..1000 {
+            assert!(physics.allows(physics.random_instruction()));
+        }
+
+        assert_eq!(physics.instruction(0, 127), Instruction { vx: 0, vy: 50 });
+        assert!(physics.allows(physics.instruction(-127, 127)));
+        assert!(!physics.allows(Instruction::new(0, 127)));
+
+        assert!(Instruction::from_reader("1\n0 127\n".as_bytes()).is_ok());
+        assert!(matches!(
+            Instruction::from_reader_with("1\n0 127\n".as_bytes(), &physics),
+            Err(InstructionParseError::TooLong(2, 50))
+        ));
+    }
+
+    #[test]
+    fn test_state_restore() {
+        let mut simulation = Simulation::load(&PathBuf::from("../../maps/test.txt")).unwrap();
+        let instructions = Instruction::load(&PathBuf::from("../../test/solves/test.in")).unwrap();
+
+        let (start, rest) = instructions.split_at(instructions.len() / 2);
+
+        for &instruction in start {
+            simulation.tick(instruction);
+        }
+
+        let state = simulation.state();
+        simulation.push();
+
+        for &instruction in rest {
+            simulation.tick(instruction);
+        }
+
+        let finished = simulation.state();
+        assert!(simulation.finished());
+
+        // restoring in any of the ways should make the rest of the run identical
+        simulation.apply();
+        assert_eq!(simulation.state(), state);
+
+        simulation.simulate(&instructions);
+        simulation.pop();
+        assert_eq!(simulation.state(), state);
+
+        let mut clone = simulation.clone();
+        simulation.restore(&finished);
+        assert_eq!(simulation.state(), finished);
+
+        for &instruction in rest {
+            clone.tick(instruction);
+        }
+        assert_eq!(clone.state(), finished);
+
+        // the map itself is shared between the clones
+        assert!(Arc::ptr_eq(&simulation.map, &clone.map));
+    }
+
+    #[test]
+    fn test_simulate_until_finished() {
+        let mut simulation = Simulation::load(&PathBuf::from("../../maps/test.txt")).unwrap();
+        let mut instructions =
+            Instruction::load(&PathBuf::from("../../test/solves/test.in")).unwrap();
+
+        let ticks = simulation.simulate_until_finished(&instructions).unwrap();
+        let finished = simulation.state();
+
+        assert!(ticks <= instructions.len());
+
+        // it stops right on the tick that reached the last goal
+        simulation.simulate(&instructions[..ticks - 1]);
+        assert!(!simulation.finished());
+        simulation.simulate(&instructions[..ticks]);
+        assert_eq!(simulation.state(), finished);
+
+        // trailing instructions don't matter
+        instructions.extend([Instruction::new(MAX_ACCELERATION, 0); 10]);
+        assert_eq!(
+            simulation.simulate_until_finished(&instructions),
+            Some(ticks)
+        );
+
+        let mut runner = Runner::new(&simulation.map);
+        assert_eq!(runner.simulate_until_finished(&instructions), Some(ticks));
+        assert_eq!(runner.state, finished);
+
+        assert_eq!(
+            runner.simulate_until_finished(&instructions[..ticks - 1]),
+            None
+        );
+
+        // a map without goals is finished right away
+        let mut empty = Simulation::from_str(
+            "0 0 1
+-10 -10 10 10
+0
+0",
+        )
+        .unwrap();
+        assert_eq!(empty.simulate_until_finished(&[]), Some(0));
+    }
+
+    #[test]
+    fn test_runners_share_map() {
+        let map = Map::load(&PathBuf::from("../../maps/sprint.txt")).unwrap();
+        let mut simulation = Simulation::from(map.clone());
+
+        let runs = (0..8)
+            .map(|_| (0..1000).map(|_| Instruction::random()).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+
+        // runners borrowing the same map can run on multiple threads
+        let results = std::thread::scope(|scope| {
+            let handles = runs
+                .iter()
+                .map(|instructions| {
+                    scope.spawn(|| {
+                        let mut runner = Runner::new(&map);
+                        let results = runner.simulate(instructions);
+
+                        (results, runner.state)
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            handles
+                .into_iter()
+                .map(|handle| handle.join().unwrap())
+                .collect::<Vec<_>>()
+        });
+
+        for (instructions, (tick_results, state)) in runs.iter().zip(results) {
+            assert_eq!(simulation.simulate(instructions), tick_results);
+            assert_eq!(simulation.state(), state);
+        }
+    }
+
+    #[test]
+    fn test_simulate_batch_deterministic() {
+        let map = Map::load(&PathBuf::from("../../maps/sprint.txt")).unwrap();
+
+        let runs = (0..64)
+            .map(|i| {
+                (0..i * 50)
+                    .map(|_| Instruction::random())
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+
+        let batch = map.simulate_batch(&runs);
+
+        for (instructions, result) in runs.iter().zip(&batch) {
+            let mut runner = Runner::new(&map);
+
+            assert_eq!(runner.simulate(instructions), result.tick_results);
+            assert_eq!(runner.state, result.state);
+        }
+
+        // continuing runs should be the same as running them in one go
+        let halves = runs
+            .iter()
+            .zip(map.simulate_batch(&runs.iter().map(|r| &r[..r.len() / 2]).collect::<Vec<_>>()))
+            .map(|(instructions, result)| (result.state, &instructions[instructions.len() / 2..]))
+            .collect::<Vec<_>>();
+
+        for (continued, result) in map.simulate_batch_from(&halves).iter().zip(&batch) {
+            assert_eq!(continued.state, result.state);
+        }
+    }
+
+    #[test]
+    fn test_racer_radius_independent() {
+        let map = Map::load(&PathBuf::from("../../maps/marathon.txt")).unwrap();
+
+        // a single huge cell, so every object is checked each tick
+        let linear = Map {
+            _asteroid_grid: Grid::new(&map.asteroids, 1 << 40),
+            _goal_grid: Grid::new(&map.goals, 1 << 40),
+            ..map.clone()
+        };
+
+        let mut rng = StdRng::seed_from_u64(0);
+
+        for radius in [1, 1000, 25_000, 100_000] {
+            let mut state = map.initial_state();
+            state.racer.radius = radius;
+
+            let instructions = (0..2000)
+                .map(|_| Instruction::new(rng.random::<InstType>(), rng.random::<InstType>()))
+                .collect::<Vec<_>>();
+
+            assert_eq!(
+                map.simulate_from(state.clone(), &instructions),
+                linear.simulate_from(state, &instructions),
+            );
+        }
+    }
+
+    const SMALL_MAP: &str = "0 0 5\n-100 -100 100 100\n2\n10 -10 10\n20 20 50\n1\n100 100 10\n";
+
+    #[test]
+    fn test_map_from_str() {
+        let simulation = SMALL_MAP.parse::<Simulation>().unwrap();
+
+        assert_eq!(simulation.racer.radius, 5);
+        assert_eq!(simulation.map.bbox.max_y, 100);
+        assert_eq!(simulation.map.asteroids.len(), 2);
+        assert_eq!(
+            simulation.map.goals[0],
+            Goal {
+                x: 100,
+                y: 100,
+                radius: 10
+            }
+        );
+
+        // trailing empty lines are fine
+        assert!(format!("{}\n\n", SMALL_MAP).parse::<Simulation>().is_ok());
+    }
+
+    #[test]
+    fn test_map_round_trip() {
+        for map in ["test", "sprint", "marathon"] {
+            let path = PathBuf::from(format!("../../maps/{}.txt", map));
+            let mut simulation = Simulation::load(&path).unwrap();
+
+            // the racer's current position shouldn't matter, only the initial one
+            simulation.tick(Instruction::new(0, MAX_ACCELERATION));
+
+            let mut buffer = vec![];
+            simulation.to_writer(&mut buffer).unwrap();
+
+            assert_eq!(
+                String::from_utf8(buffer).unwrap(),
+                fs::read_to_string(&path).unwrap()
+            );
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_map_json_round_trip() {
+        let simulation = Simulation::load(&PathBuf::from("../../maps/sprint.txt")).unwrap();
+        let description = simulation.description();
+
+        let json = serde_json::to_string(&description).unwrap();
+        let loaded = serde_json::from_str::<MapDescription>(&json).unwrap();
+
+        assert_eq!(loaded, description);
+        assert_eq!(Simulation::from(loaded).description(), description);
+
+        let instructions = vec![Instruction::new(1, 2), Instruction::new(-127, 0)];
+        let json = serde_json::to_string(&instructions).unwrap();
+        assert_eq!(
+            serde_json::from_str::<Vec<Instruction>>(&json).unwrap(),
+            instructions
+        );
+    }
+
+    #[test]
+    fn test_map_physics_header() {
+        let map = format!("physics drag=19/20 cell_size=500\n{}", SMALL_MAP)
+            .parse::<Map>()
+            .unwrap();
+
+        let physics = PhysicsConfig {
+            drag_fraction: (19, 20),
+            cell_size: 500,
+            ..PhysicsConfig::default()
+        };
+        assert_eq!(map.physics, physics);
+
+        // only non-default maps have a header
+        let mut buffer = vec![];
+        map.to_writer(&mut buffer).unwrap();
+        let text = String::from_utf8(buffer).unwrap();
+
+        assert!(text.starts_with("physics drag=19/20 cell_size=500\n"));
+        assert_eq!(text.parse::<Map>().unwrap().physics, physics);
+        assert!(PhysicsConfig::default().to_header().is_none());
+
+        // less drag means the racer keeps more of its speed
+        for (header, speed) in [("", 90), ("physics drag=19/20", 95)] {
+            let map = format!("{}\n0 0 5\n-1000 -1000 1000 1000\n0\n0\n", header)
+                .trim_start()
+                .parse::<Map>()
+                .unwrap();
+
+            let mut state = map.initial_state();
+            map.tick(&mut state, Instruction::new(100, 0));
+            map.tick(&mut state, Instruction::new(0, 0));
+
+            assert_eq!(state.racer.vx, speed);
+        }
+    }
+
+    #[test]
+    fn test_map_physics_errors() {
+        fn error(header: &str) -> MapParseError {
+            format!("{}\n{}", header, SMALL_MAP)
+                .parse::<Map>()
+                .unwrap_err()
+        }
+
+        assert!(
+            matches!(error("physics gravity=1"), MapParseError::BadPhysics(1, s) if s == "gravity")
+        );
+        assert!(
+            matches!(error("physics drag=1/0"), MapParseError::BadPhysics(1, s) if s == "drag")
+        );
+        assert!(matches!(
+            error("physics drag=11/10"),
+            MapParseError::BadPhysics(1, _)
+        ));
+        assert!(matches!(
+            error("physics collision=1"),
+            MapParseError::BadPhysics(1, _)
+        ));
+        assert!(matches!(
+            error("physics max_acceleration=200"),
+            MapParseError::BadPhysics(1, _)
+        ));
+        assert!(matches!(
+            error("physics cell_size=0"),
+            MapParseError::BadPhysics(1, _)
+        ));
+        assert!(matches!(
+            error("physics nope"),
+            MapParseError::BadPhysics(1, _)
+        ));
+
+        // the line numbers after the header are still correct
+        assert!(matches!(
+            "physics\n0 0 5\n-100 -100 100\n"
+                .parse::<Map>()
+                .unwrap_err(),
+            MapParseError::FieldCount(3, 4, 3)
+        ));
+    }
+
+    #[test]
+    fn test_map_parse_errors() {
+        fn error(map: &str) -> MapParseError {
+            map.parse::<Simulation>().unwrap_err()
+        }
+
+        assert!(matches!(error(""), MapParseError::MissingLine(1)));
+        assert!(matches!(error("0 0 5\n"), MapParseError::MissingLine(2)));
+        assert!(matches!(error("0 0\n"), MapParseError::FieldCount(1, 3, 2)));
+        assert!(matches!(
+            error("0 0 5\n-100 -100 100 100 7\n"),
+            MapParseError::FieldCount(2, 4, 5)
+        ));
+        assert!(matches!(
+            error("0 0 5\n-100 -100 100 1e2\n"),
+            MapParseError::BadInteger(2, field) if field == "1e2"
+        ));
+        assert!(matches!(
+            error("0 0 5\n-100 -100 100 100\n-1\n"),
+            MapParseError::BadInteger(3, _)
+        ));
+        assert!(matches!(
+            error("0 0 5\n-100 -100 100 100\n3\n10 -10 10\n20 20 50\n"),
+            MapParseError::CountMismatch(3, 3, 2)
+        ));
+        assert!(matches!(
+            error("0 0 5\n-100 -100 100 100\n0\n1\n1 2 x\n"),
+            MapParseError::BadInteger(5, _)
+        ));
+        assert!(matches!(
+            error(&format!("{}\n5 5 5\n", SMALL_MAP)),
+            MapParseError::TrailingGarbage(9)
+        ));
+
+        assert!(matches!(
+            Simulation::load(&PathBuf::from("does/not/exist.txt")),
+            Err(MapParseError::Io(_))
+        ));
+    }
+
+    #[test]
+    fn test_asteroid_at() {
+        let map = Map::load(&PathBuf::from("../../maps/sprint.txt")).unwrap();
+        let mut rng = StdRng::seed_from_u64(0);
+
+        for _ in 0..10_000 {
+            let x = rng.random_range(map.bbox.min_x..=map.bbox.max_x);
+            let y = rng.random_range(map.bbox.min_y..=map.bbox.max_y);
+
+            let expected = (map.asteroids.iter()).position(|asteroid| {
+                euclidean_distance(x, y, asteroid.x, asteroid.y) <= asteroid.radius
+            });
+
+            assert_eq!(map.asteroid_at(x, y), expected);
+        }
+
+        // the edge is inside
+        let asteroid = map.asteroids[0];
+        let edge = (asteroid.x + asteroid.radius, asteroid.y);
+        assert!(map.asteroid_at(edge.0, edge.1).is_some());
+    }
+
+    #[test]
+    fn test_push_from_asteroids_large_coordinates() {
+        let asteroid = Asteroid {
+            x: 0,
+            y: 0,
+            radius: 100_000_000_000,
+        };
+        let racer = Racer {
+            x: 1_000_000_000,
+            y: 0,
+            vx: 0,
+            vy: 0,
+            radius: 1,
+        };
+
+        // the push is about 10^11 and so is the distance, so their product overflows 64 bits
+        let physics = PhysicsConfig {
+            cell_size: 1 << 40,
+            ..PhysicsConfig::default()
+        };
+        let bbox = BoundingBox {
+            min_x: -1 << 50,
+            min_y: -1 << 50,
+            max_x: 1 << 50,
+            max_y: 1 << 50,
+        };
+        let map = Map::with_physics(racer, vec![asteroid], vec![], bbox, physics);
+
+        let mut state = map.initial_state();
+        let result = map.tick(&mut state, Instruction::new(0, 0));
+
+        assert_ne!(result & TickFlag::COLLIDED, 0);
+        assert_eq!(state.racer.x, asteroid.radius + racer.radius);
+        assert_eq!(state.racer.y, 0);
+    }
 
-        Self::new(racer, asteroids, goals, bbox)
+    #[test]
+    fn test_euclidean_distance_extremes() {
+        assert_eq!(euclidean_distance(0, 0, 0, 0), 0);
+        assert_eq!(euclidean_distance(3, 4, 0, 0), 5);
+        assert_eq!(euclidean_distance(-3, -4, 0, 0), 5);
+
+        // 94906265^2 is just above 2^53, where the float square root starts rounding
+        assert_eq!(euclidean_distance(94906265, 0, 0, 0), 94906265);
+        assert_eq!(euclidean_distance(94906265, 1, 0, 0), 94906265);
+        assert_eq!(euclidean_distance(PosType::MAX, 0, 0, 0), PosType::MAX);
+        assert_eq!(euclidean_distance(PosType::MIN + 1, 0, 0, 0), PosType::MAX);
+
+        // doesn't fit, so we saturate
+        assert_eq!(euclidean_distance(PosType::MIN, 0, 0, 0), PosType::MAX);
+        assert_eq!(
+            euclidean_distance(PosType::MIN, PosType::MIN, PosType::MAX, PosType::MAX),
+            PosType::MAX
+        );
     }
 }
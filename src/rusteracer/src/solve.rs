@@ -1,25 +1,28 @@
-use crate::simulation::{Instruction, PosType, Simulation};
-use rand::{random, Rng, RngCore};
+use crate::opendata::SolverContext;
+use crate::simulation::{euclidean_distance, Instruction, Map, PhysicsConfig, PosType, SimState};
+use rand::Rng;
 use std::cmp::Ordering;
 use std::collections::BinaryHeap;
 use std::f64;
 use std::fs::File;
 use std::io::{self, BufRead};
 use std::path::PathBuf;
+use std::process;
 
-pub fn load_asteroid_graph(
-    path: &PathBuf,
-) -> io::Result<(
+/// Vertex positions, edges and the objects (racer/asteroid/goal) each vertex belongs to.
+pub type AsteroidGraph = (
     Vec<(PosType, PosType)>,
     Vec<(usize, usize)>,
     Vec<(char, usize)>,
-)> {
+);
+
+pub fn load_asteroid_graph(path: &PathBuf) -> io::Result<AsteroidGraph> {
     let file = File::open(path)?;
     let reader = io::BufReader::new(file);
 
     let contents: Vec<String> = reader
         .lines()
-        .filter_map(Result::ok)
+        .map_while(Result::ok)
         .filter(|line| !line.starts_with('#') && !line.trim().is_empty())
         .collect();
 
@@ -94,15 +97,15 @@ impl PartialOrd for State {
 }
 
 pub fn shortest_path(
-    vertices: &Vec<(i64, i64)>,
-    edges: &Vec<(usize, usize)>,
-    vertex_objects: &Vec<(char, usize)>,
+    vertices: &[(i64, i64)],
+    edges: &[(usize, usize)],
+    vertex_objects: &[(char, usize)],
 ) -> Option<(f64, Vec<usize>)> {
     let start = 0;
     let goals: Vec<usize> = vertex_objects
         .iter()
         .enumerate()
-        .filter(|(i, (c, _))| *c == 'G')
+        .filter(|(_, (c, _))| *c == 'G')
         .map(|(i, _)| i)
         .collect();
 
@@ -159,8 +162,8 @@ pub fn shortest_path(
 }
 
 pub fn closest_distance_to_path(
-    path: &Vec<usize>,
-    vertices: &Vec<(PosType, PosType)>,
+    path: &[usize],
+    vertices: &[(PosType, PosType)],
     point: (i64, i64),
 ) -> f64 {
     let mut min_dist = f64::INFINITY;
@@ -232,38 +235,66 @@ pub fn closest_distance_to_path(
 
 #[derive(Clone, Debug)]
 pub(crate) struct Individual {
-    pub(crate) simulation: Simulation,
+    pub(crate) state: SimState,
     pub(crate) instructions: Vec<Instruction>,
     pub(crate) fitness: f64,
 }
 
 impl Individual {
-    pub(crate) fn new(simulation: Simulation, instructions: Vec<Instruction>) -> Self {
+    pub(crate) fn new(state: SimState, instructions: Vec<Instruction>) -> Self {
         Individual {
-            simulation,
+            state,
             instructions,
             fitness: 0.0,
         }
     }
 
-    pub(crate) fn mutate(&mut self) {
+    /// Random instructions (valid for the physics of the map) to append to this individual.
+    ///
+    /// Mutating is done in two steps so that the mutations of a whole population can be
+    /// simulated at once (see [`crate::simulation::Map::simulate_batch_from`]): the returned
+    /// instructions are simulated from [`Individual::state`], and the result is then given to
+    /// [`Individual::apply_mutation`].
+    pub(crate) fn random_mutation(&self, physics: &PhysicsConfig) -> Vec<Instruction> {
         let mut rng = rand::rng();
 
-        let instruction = Instruction::random();
+        let instruction = physics.random_instruction();
+        vec![instruction; (rng.random::<f64>() * 10.0) as usize]
+    }
 
-        for _ in 0..(rng.random::<f64>() * 10.0) as usize {
-            self.instructions.push(instruction);
-            self.simulation.tick(instruction);
-        }
+    /// Append the instructions of a mutation along with the state they lead to.
+    ///
+    /// The fitness is not updated, see [`Individual::evaluate_fitness`].
+    pub(crate) fn apply_mutation(&mut self, appended: &[Instruction], state: SimState) {
+        self.instructions.extend(appended);
+        self.state = state;
     }
 
-    pub(crate) fn evaluate_fitness(
-        &mut self,
-        path: &Vec<usize>,
-        vertices: &Vec<(PosType, PosType)>,
-    ) {
+    /// Fitness by the reached goals, the closer to the nearest unreached goal the better.
+    ///
+    /// Unlike [`Individual::evaluate_fitness`], this needs no asteroid graph. Once all goals
+    /// are reached, shorter solutions are better.
+    pub(crate) fn evaluate_reach(&mut self, map: &Map) {
+        let racer = &self.state.racer;
+        let reached = self.state.reached_goals.iter().filter(|&&r| r).count();
+
+        let nearest = (map.goals.iter())
+            .zip(&self.state.reached_goals)
+            .filter(|(_, &reached)| !reached)
+            .map(|(goal, _)| euclidean_distance(racer.x, racer.y, goal.x, goal.y))
+            .min();
+
+        self.fitness = reached as f64
+            + match nearest {
+                // at most 1, so reaching another goal is always better
+                Some(distance) => 1.0 / (1.0 + distance as f64 / map.physics.cell_size as f64),
+                None => 1.0 + 1.0 / (1.0 + self.instructions.len() as f64),
+            };
+    }
+
+    pub(crate) fn evaluate_fitness(&mut self, path: &[usize], vertices: &[(PosType, PosType)]) {
         self.fitness =
-            closest_distance_to_path(path, vertices, (self.simulation.racer.x, self.simulation.racer.y));
+            closest_distance_to_path(path, vertices, (self.state.racer.x, self.state.racer.y));
     }
 }
 
@@ -271,3 +302,94 @@ pub(crate) fn select_best(population: &mut Vec<Individual>, num_best: usize) {
     population.sort_by(|a, b| b.fitness.partial_cmp(&a.fitness).unwrap_or(Ordering::Equal));
     population.truncate(num_best);
 }
+
+/// One generation of the genetic algorithm: every individual gets `mutation_count` random
+/// mutations (simulated in parallel with the `rayon` feature), which are evaluated by
+/// `evaluate` and compete with the current population for its `population_size` places.
+pub(crate) fn next_generation(
+    map: &Map,
+    population: Vec<Individual>,
+    population_size: usize,
+    mutation_count: usize,
+    evaluate: impl Fn(&mut Individual),
+) -> Vec<Individual> {
+    let mut parents = Vec::new();
+    let mut mutations = Vec::new();
+
+    for individual in &population {
+        for _ in 0..mutation_count {
+            parents.push(individual);
+            mutations.push((
+                individual.state.clone(),
+                individual.random_mutation(&map.physics),
+            ));
+        }
+    }
+
+    let results = map.simulate_batch_from(&mutations);
+
+    let mut new_population: Vec<Individual> = (parents.into_iter())
+        .zip(mutations)
+        .zip(results)
+        .map(|((parent, (_, appended)), result)| {
+            let mut mutated_individual = parent.clone();
+            mutated_individual.apply_mutation(&appended, result.state);
+            evaluate(&mut mutated_individual);
+            mutated_individual
+        })
+        .collect();
+
+    let mut combined_population = population;
+    combined_population.append(&mut new_population);
+
+    select_best(&mut combined_population, population_size);
+
+    combined_population
+}
+
+/// Evolves instructions by [`Individual::evaluate_reach`] until `done(generation)`.
+pub(crate) fn evolve_by_max_reach(map: &Map, done: impl Fn(usize) -> bool) -> Individual {
+    let population_size = 10;
+    let mutation_count = 10;
+
+    let mut population = vec![Individual::new(map.initial_state(), vec![])];
+    population[0].evaluate_reach(map);
+
+    let mut generation = 0;
+    while !done(generation) {
+        population = next_generation(
+            map,
+            population,
+            population_size,
+            mutation_count,
+            |individual| individual.evaluate_reach(map),
+        );
+        generation += 1;
+    }
+
+    population.swap_remove(0)
+}
+
+/// Anytime solver: evolves instructions until the deadline (or for a fixed number of
+/// generations without a time limit) and prints the best ones.
+pub fn solve_by_max_reach(mut context: SolverContext) {
+    let generations = 1000;
+
+    let map = match Map::from_reader(&mut context.input) {
+        Ok(map) => map,
+        Err(e) => {
+            eprintln!("Error: could not load the map: {}", e);
+            process::exit(1);
+        }
+    };
+
+    let best = evolve_by_max_reach(&map, |generation| match context.deadline {
+        Some(_) => context.out_of_time(),
+        None => generation >= generations,
+    });
+
+    if let Err(e) = Instruction::to_writer(io::stdout().lock(), &best.instructions) {
+        eprintln!("Error: could not print the instructions: {}", e);
+        process::exit(1);
+    }
+}